@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use bevy_prng::WyRand;
+use bevy_rand::prelude::{EntropyComponent, GlobalEntropy};
+
+use crate::{
+    battle::{self, Action, EnemyState, TurnStep},
+    enemy::Enemy,
+    hero::Player,
+    ActionEvent, AppState, BattleState, InfoPanelState,
+};
+
+#[derive(Clone, Copy, Debug, Resource)]
+pub struct RunSeed(pub u64);
+
+impl RunSeed {
+    // Uses `--seed <number>` when given, otherwise a fresh random seed
+    pub fn from_args() -> Self {
+        let seed = std::env::args()
+            .skip_while(|arg| arg != "--seed")
+            .nth(1)
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or_else(rand::random);
+
+        Self(seed)
+    }
+}
+
+// Every run starts the shared rng over from its seed
+pub fn seed_run(mut global_entropy: ResMut<GlobalEntropy<WyRand>>, seed: Res<RunSeed>) {
+    global_entropy.reseed(seed.0.to_le_bytes());
+
+    info!("Run seed: {} (replay with --seed {})", seed.0, seed.0);
+}
+
+// Only the first run uses the seed the game was started with
+fn reroll_seed(mut seed: ResMut<RunSeed>) {
+    seed.0 = rand::random();
+}
+
+// The enemies' side of the round, stepped through an action at a time
+#[derive(Default, Deref, DerefMut, Resource)]
+pub struct EnemyTurn(pub battle::EnemyTurn);
+
+// Moves the enemies' side of the round on by one step
+#[derive(Event)]
+pub struct EnemyAct;
+
+fn enemy_states(enemy_query: &Query<(&Enemy, &mut EntropyComponent<WyRand>)>) -> Vec<EnemyState> {
+    let mut enemies = enemy_query
+        .iter()
+        .map(|(enemy, _)| enemy)
+        .collect::<Vec<_>>();
+
+    enemies.sort_by_key(|enemy| enemy.index);
+
+    enemies
+        .into_iter()
+        .map(|enemy| enemy.state.clone())
+        .collect()
+}
+
+fn enemy_turn(
+    mut enemy_act_writer: EventWriter<EnemyAct>,
+    enemy_query: Query<(&Enemy, &mut EntropyComponent<WyRand>)>,
+    mut enemy_turn: ResMut<EnemyTurn>,
+) {
+    enemy_turn.0 = battle::EnemyTurn::new(&enemy_states(&enemy_query));
+
+    enemy_act_writer.send(EnemyAct);
+}
+
+// Enemies settle on their next move as the heroes' turn starts, including any
+// that only just arrived or woke up
+fn plan_enemies(
+    mut enemy_query: Query<(&mut Enemy, &mut EntropyComponent<WyRand>)>,
+    player: Res<Player>,
+) {
+    for (mut enemy, mut rng) in enemy_query.iter_mut() {
+        enemy.plan(&player, &mut *rng);
+    }
+}
+
+// The same steps `BattleState::run_round` takes, with a pause after each
+fn enemy_act(
+    mut action_event_writer: EventWriter<ActionEvent>,
+    mut enemy_query: Query<(&Enemy, &mut EntropyComponent<WyRand>)>,
+    mut enemy_turn: ResMut<EnemyTurn>,
+    mut next_battle_state: ResMut<NextState<BattleState>>,
+    mut next_info_state: ResMut<NextState<InfoPanelState>>,
+    player: Res<Player>,
+) {
+    match enemy_turn.next(&enemy_states(&enemy_query)) {
+        Some(TurnStep::Enemy(index)) => {
+            for (enemy, mut rng) in enemy_query.iter_mut() {
+                if enemy.index == index {
+                    action_event_writer
+                        .send(ActionEvent(enemy.choose_action(index, &player, &mut *rng)));
+                }
+            }
+        }
+        // Statuses tick over before the heroes go again
+        Some(TurnStep::EndRound) => {
+            action_event_writer.send(ActionEvent(Action::EndRound));
+        }
+        None => {
+            next_battle_state.set(BattleState::Player);
+            next_info_state.set(InfoPanelState::Menu);
+        }
+    }
+}
+
+pub struct EnemyAiPlugin;
+
+impl Plugin for EnemyAiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EnemyAct>()
+            .init_resource::<EnemyTurn>()
+            .add_systems(OnExit(AppState::Run), reroll_seed)
+            .add_systems(OnEnter(BattleState::Enemy), enemy_turn)
+            .add_systems(
+                Update,
+                (
+                    enemy_act.run_if(on_event::<EnemyAct>()),
+                    plan_enemies.run_if(in_state(BattleState::Player)),
+                ),
+            );
+    }
+}
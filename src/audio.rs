@@ -0,0 +1,250 @@
+use bevy::{
+    audio::{PlaybackMode, Volume},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    battle::BattleOutcome,
+    hero::HeroCycled,
+    input::{InputAction, InputActions},
+    is_persistent,
+    save::{read_stored, write_stored},
+    status::StatusKind,
+    tower::{Floor, TowerDefinition, TowerHandle},
+    AppState, OutcomeEvent, Persistent,
+};
+
+const SETTINGS_PATH: &str = "audio.ron";
+
+// Steps the options menu moves the volume by
+pub const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Clone, Debug, Deserialize, Resource, Serialize)]
+pub struct AudioSettings {
+    pub is_muted: bool,
+    pub music_volume: f32,
+    pub sound_volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            is_muted: false,
+            music_volume: 0.5,
+            sound_volume: 0.8,
+        }
+    }
+}
+
+impl AudioSettings {
+    fn load() -> Self {
+        let Some(contents) = read_stored(SETTINGS_PATH) else {
+            return Self::default();
+        };
+
+        ron::from_str(&contents).unwrap_or_else(|error| {
+            warn!("Could not read {}: {}", SETTINGS_PATH, error);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) {
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| write_stored(SETTINGS_PATH, &contents));
+
+        if let Err(error) = result {
+            warn!("Could not write {}: {}", SETTINGS_PATH, error);
+        }
+    }
+
+    fn music(&self) -> f32 {
+        if self.is_muted {
+            0.0
+        } else {
+            self.music_volume
+        }
+    }
+
+    fn sound(&self) -> f32 {
+        if self.is_muted {
+            0.0
+        } else {
+            self.sound_volume
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Sounds {
+    block: Handle<AudioSource>,
+    cycle: Handle<AudioSource>,
+    death: Handle<AudioSource>,
+    heal: Handle<AudioSource>,
+    hit: Handle<AudioSource>,
+    shield: Handle<AudioSource>,
+}
+
+impl Sounds {
+    fn for_outcome(&self, outcome: &BattleOutcome) -> Option<&Handle<AudioSource>> {
+        match *outcome {
+            BattleOutcome::Absorbed {
+                kind: StatusKind::Shield,
+                ..
+            } => Some(&self.shield),
+            BattleOutcome::Absorbed { .. } => Some(&self.block),
+            // Whatever soaked it up has already made a sound
+            BattleOutcome::Damaged { amount: 0, .. } => None,
+            BattleOutcome::Damaged { .. } => Some(&self.hit),
+            BattleOutcome::Defeated { .. } => Some(&self.death),
+            BattleOutcome::Healed { .. } => Some(&self.heal),
+            BattleOutcome::StatusApplied {
+                kind: StatusKind::Block,
+                ..
+            } => Some(&self.block),
+            BattleOutcome::StatusApplied {
+                kind: StatusKind::Shield,
+                ..
+            } => Some(&self.shield),
+            _ => None,
+        }
+    }
+}
+
+// The track for the current floor, by path so a new floor can tell whether
+// it needs to change
+#[derive(Component)]
+struct Music(String);
+
+fn load_sounds(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(Sounds {
+        block: asset_server.load("audio/sounds/block.wav"),
+        cycle: asset_server.load("audio/sounds/cycle.wav"),
+        death: asset_server.load("audio/sounds/death.wav"),
+        heal: asset_server.load("audio/sounds/heal.wav"),
+        hit: asset_server.load("audio/sounds/hit.wav"),
+        shield: asset_server.load("audio/sounds/shield.wav"),
+    });
+}
+
+fn load_audio_settings(mut settings: ResMut<AudioSettings>) {
+    *settings = AudioSettings::load();
+}
+
+fn play_sound(commands: &mut Commands, sound: &Handle<AudioSource>, settings: &AudioSettings) {
+    if settings.sound() <= 0.0 {
+        return;
+    }
+
+    commands.spawn(AudioBundle {
+        source: sound.clone(),
+        settings: PlaybackSettings::DESPAWN.with_volume(Volume::new(settings.sound())),
+    });
+}
+
+fn play_music(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    floor: Res<Floor>,
+    music_query: Query<(Entity, &Music)>,
+    settings: Res<AudioSettings>,
+    tower_definitions: Res<Assets<TowerDefinition>>,
+    tower_handle: Res<TowerHandle>,
+) {
+    let Some(path) = tower_definitions
+        .get(&tower_handle.0)
+        .and_then(|tower| tower.music(floor.current))
+    else {
+        return;
+    };
+
+    if music_query.iter().any(|(_, music)| music.0 == path) {
+        return;
+    }
+
+    for (entity_id, _) in music_query.iter() {
+        commands.entity(entity_id).despawn();
+    }
+
+    let path = path.to_string();
+
+    commands.spawn((
+        StateScoped(AppState::Run),
+        AudioBundle {
+            source: asset_server.load(path.clone()),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Loop,
+                volume: Volume::new(settings.music()),
+                ..default()
+            },
+        },
+        Music(path),
+    ));
+}
+
+fn music_volume(music_query: Query<&AudioSink, With<Music>>, settings: Res<AudioSettings>) {
+    for sink in music_query.iter() {
+        sink.set_volume(settings.music());
+    }
+}
+
+fn outcome_sounds(
+    mut commands: Commands,
+    mut outcome_event_reader: EventReader<OutcomeEvent>,
+    settings: Res<AudioSettings>,
+    sounds: Res<Sounds>,
+) {
+    for event in outcome_event_reader.read() {
+        if let Some(sound) = sounds.for_outcome(event) {
+            play_sound(&mut commands, sound, &settings);
+        }
+    }
+}
+
+fn cycle_sound(
+    mut commands: Commands,
+    mut hero_cycled_reader: EventReader<HeroCycled>,
+    settings: Res<AudioSettings>,
+    sounds: Res<Sounds>,
+) {
+    for _ in hero_cycled_reader.read() {
+        play_sound(&mut commands, &sounds.cycle, &settings);
+    }
+}
+
+fn toggle_mute(
+    input_actions: Res<InputActions>,
+    persistent: Res<Persistent>,
+    mut settings: ResMut<AudioSettings>,
+) {
+    if !input_actions.just_pressed(InputAction::Mute) {
+        return;
+    }
+
+    settings.is_muted = !settings.is_muted;
+
+    if persistent.0 {
+        settings.save();
+    }
+}
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AudioSettings>()
+            .add_systems(
+                Startup,
+                (load_sounds, load_audio_settings.run_if(is_persistent)),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_mute,
+                    music_volume.run_if(resource_changed::<AudioSettings>),
+                    (play_music, outcome_sounds, cycle_sound).run_if(in_state(AppState::Run)),
+                ),
+            );
+    }
+}
@@ -0,0 +1,1038 @@
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::status::{StatusEffect, StatusKind, Statuses, TurnStartEffect};
+
+pub const HAND_SIZE: usize = 4;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum EffectTarget {
+    Target,
+    User,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum Effect {
+    Damage {
+        target: EffectTarget,
+        amount: isize,
+    },
+    Heal {
+        target: EffectTarget,
+        amount: isize,
+    },
+    Status {
+        target: EffectTarget,
+        status: StatusEffect,
+    },
+}
+
+// Who the player has to pick before the action goes off
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ActionTarget {
+    Ally,
+    Enemy,
+    #[default]
+    User,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ActionDefinition {
+    pub card: usize,
+    #[serde(default = "default_copies")]
+    pub copies: usize,
+    pub effects: Vec<Effect>,
+    pub name: String,
+    #[serde(default)]
+    pub target: ActionTarget,
+}
+
+fn default_copies() -> usize {
+    1
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AttackTarget {
+    #[default]
+    Current,
+    Random,
+    Weakest,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EnemyAttack {
+    // Statuses the enemy gives itself
+    #[serde(default)]
+    pub buffs: Vec<StatusEffect>,
+    pub damage: isize,
+    #[serde(default)]
+    pub inflicts: Vec<StatusEffect>,
+    pub name: String,
+    #[serde(default)]
+    pub target: AttackTarget,
+    pub weight: u32,
+}
+
+// What an enemy's next move looks like to the player
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IntentKind {
+    Attack,
+    Buff,
+    Debuff,
+    Defend,
+}
+
+impl EnemyAttack {
+    // Moves that only touch the enemy itself leave the heroes alone
+    pub fn hits_heroes(&self) -> bool {
+        self.damage > 0 || !self.inflicts.is_empty()
+    }
+
+    pub fn kind(&self) -> IntentKind {
+        if self.damage > 0 {
+            IntentKind::Attack
+        } else if !self.inflicts.is_empty() {
+            IntentKind::Debuff
+        } else if self
+            .buffs
+            .iter()
+            .any(|status| status.kind.definition().defends)
+        {
+            IntentKind::Defend
+        } else {
+            IntentKind::Buff
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Action {
+    Enemy {
+        enemy: usize,
+        attack: usize,
+        target: usize,
+    },
+    Hero {
+        hero: usize,
+        action: usize,
+        target: Target,
+    },
+    Rest {
+        hero: usize,
+    },
+    EndRound,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Target {
+    Enemy(usize),
+    Hero(usize),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BattleOutcome {
+    // Damage a block or shield soaked up before the rest got through
+    Absorbed {
+        target: Target,
+        kind: StatusKind,
+        amount: isize,
+    },
+    Damaged {
+        target: Target,
+        amount: isize,
+    },
+    Defeated {
+        target: Target,
+    },
+    HealFailed {
+        target: Target,
+    },
+    Healed {
+        target: Target,
+        amount: isize,
+    },
+    StatusApplied {
+        target: Target,
+        kind: StatusKind,
+    },
+    Lost,
+    Won,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HeroState {
+    pub actions: Vec<ActionDefinition>,
+    pub current_hp: isize,
+    pub max_hp: isize,
+    pub name: String,
+    pub statuses: Statuses,
+}
+
+impl HeroState {
+    pub fn new(name: &str, hp: isize, actions: Vec<ActionDefinition>) -> Self {
+        Self {
+            actions,
+            current_hp: hp,
+            max_hp: hp,
+            name: name.to_string(),
+            statuses: Statuses::default(),
+        }
+    }
+
+    pub fn can_act(&self) -> bool {
+        !self.is_dead() && !self.statuses.prevents_action()
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current_hp <= 0
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PartyState {
+    pub current_hero: usize,
+    pub heroes: Vec<HeroState>,
+}
+
+impl PartyState {
+    pub fn new(heroes: Vec<HeroState>) -> Self {
+        Self {
+            current_hero: 0,
+            heroes,
+        }
+    }
+
+    pub fn current(&self) -> &HeroState {
+        &self.heroes[self.current_hero]
+    }
+
+    pub fn cycle_hero(&mut self) {
+        for _ in 0..self.heroes.len() {
+            self.current_hero = (self.current_hero + 1) % self.heroes.len();
+
+            if self.current().can_act() {
+                break;
+            }
+        }
+    }
+
+    // Switches straight to a hero, if they are able to act
+    pub fn select_hero(&mut self, hero: usize) -> bool {
+        if !self.heroes.get(hero).is_some_and(HeroState::can_act) {
+            return false;
+        }
+
+        self.current_hero = hero;
+
+        true
+    }
+
+    pub fn end_turn(&mut self) {
+        self.heroes[self.current_hero]
+            .statuses
+            .apply(StatusEffect::new(StatusKind::Sleep, 0, 0));
+    }
+
+    // Awaken everyone if nobody left standing can act
+    fn wake_if_all_asleep(&mut self) {
+        let all_sleeping = self
+            .heroes
+            .iter()
+            .filter(|hero| !hero.is_dead())
+            .all(|hero| hero.statuses.prevents_action());
+
+        if all_sleeping {
+            for hero in self.heroes.iter_mut() {
+                hero.statuses.remove(StatusKind::Sleep);
+            }
+        }
+    }
+
+    pub fn is_defeated(&self) -> bool {
+        self.heroes.iter().all(HeroState::is_dead)
+    }
+
+    pub fn start_battle(&mut self) {
+        for hero in self.heroes.iter_mut() {
+            hero.statuses.clear();
+        }
+
+        if self.current().is_dead() {
+            self.cycle_hero();
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Card {
+    pub action: usize,
+    pub hero: usize,
+}
+
+impl Card {
+    pub fn is_playable(&self, party: &PartyState) -> bool {
+        party.heroes[self.hero].can_act()
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DeckState {
+    pub discard: Vec<Card>,
+    pub draw: Vec<Card>,
+    pub hand: Vec<Card>,
+}
+
+impl DeckState {
+    // Every hero puts in one card per copy of each of their actions
+    pub fn new(party: &PartyState, rng: &mut impl Rng) -> Self {
+        let mut draw = Vec::new();
+
+        for (hero, state) in party.heroes.iter().enumerate() {
+            for (action, definition) in state.actions.iter().enumerate() {
+                for _ in 0..definition.copies {
+                    draw.push(Card { action, hero });
+                }
+            }
+        }
+
+        draw.shuffle(rng);
+
+        Self {
+            discard: Vec::new(),
+            draw,
+            hand: Vec::new(),
+        }
+    }
+
+    // Throws away the old hand and draws a fresh one. Cards of fallen heroes
+    // go straight to the discard pile.
+    pub fn draw_hand(&mut self, size: usize, party: &PartyState, rng: &mut impl Rng) {
+        self.discard.append(&mut self.hand);
+
+        while self.hand.len() < size {
+            if self.draw.is_empty() {
+                let has_living = self
+                    .discard
+                    .iter()
+                    .any(|card| !party.heroes[card.hero].is_dead());
+
+                if !has_living {
+                    break;
+                }
+
+                self.draw.append(&mut self.discard);
+                self.draw.shuffle(rng);
+            }
+
+            let Some(card) = self.draw.pop() else {
+                break;
+            };
+
+            if party.heroes[card.hero].is_dead() {
+                self.discard.push(card);
+            } else {
+                self.hand.push(card);
+            }
+        }
+    }
+
+    pub fn play(&mut self, hero: usize, action: usize) {
+        let Some(index) = self
+            .hand
+            .iter()
+            .position(|card| card.hero == hero && card.action == action)
+        else {
+            return;
+        };
+
+        let card = self.hand.remove(index);
+
+        self.discard.push(card);
+    }
+}
+
+// An enemy's next move, picked before the heroes act so they can see it
+// coming. No hero means whoever is in front when the enemy gets to act
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Intent {
+    pub attack: usize,
+    pub target: Option<usize>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EnemyState {
+    pub attacks: Vec<EnemyAttack>,
+    pub current_hp: isize,
+    #[serde(default)]
+    pub intent: Option<Intent>,
+    pub max_hp: isize,
+    pub name: String,
+    pub statuses: Statuses,
+}
+
+impl EnemyState {
+    pub fn new(name: &str, hp: isize, attacks: Vec<EnemyAttack>) -> Self {
+        Self {
+            attacks,
+            current_hp: hp,
+            intent: None,
+            max_hp: hp,
+            name: name.to_string(),
+            statuses: Statuses::default(),
+        }
+    }
+
+    pub fn can_act(&self) -> bool {
+        !self.is_dead() && !self.statuses.prevents_action()
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current_hp <= 0
+    }
+
+    pub fn total_weight(&self) -> u32 {
+        self.attacks.iter().map(|attack| attack.weight).sum()
+    }
+
+    // Picks the attack a roll in `0..total_weight()` lands on
+    pub fn choose_attack(&self, mut roll: u32) -> usize {
+        for (i, attack) in self.attacks.iter().enumerate() {
+            if roll < attack.weight {
+                return i;
+            }
+
+            roll -= attack.weight;
+        }
+
+        self.attacks.len().saturating_sub(1)
+    }
+
+    pub fn choose_intent(&self, party: &PartyState, rng: &mut impl Rng) -> Intent {
+        // Files without any weight are turned away when loaded, but the last
+        // attack beats a panic
+        let roll = match self.total_weight() {
+            0 => 0,
+            total => rng.gen_range(0..total),
+        };
+        let attack = self.choose_attack(roll);
+
+        let living = party
+            .heroes
+            .iter()
+            .enumerate()
+            .filter(|(_, hero)| !hero.is_dead())
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        let target = match self.attacks[attack].target {
+            AttackTarget::Current => None,
+            AttackTarget::Random => living.choose(rng).copied(),
+            AttackTarget::Weakest => living
+                .iter()
+                .copied()
+                .min_by_key(|hero| party.heroes[*hero].current_hp),
+        };
+
+        Intent { attack, target }
+    }
+
+    // Settles on the next move as the heroes' turn starts, unless one is
+    // already chosen or the enemy can't act
+    pub fn plan(&mut self, party: &PartyState, rng: &mut impl Rng) {
+        if self.intent.is_none() && self.can_act() {
+            self.intent = Some(self.choose_intent(party, rng));
+        }
+    }
+
+    // Who the intent would hit right now, falling back to whoever is in front
+    // once the chosen hero has fallen
+    pub fn intended_target(&self, intent: &Intent, party: &PartyState) -> usize {
+        intent
+            .target
+            .filter(|hero| !party.heroes[*hero].is_dead())
+            .unwrap_or(party.current_hero)
+    }
+
+    // Carries out the intent when there is one, otherwise decides on the spot
+    pub fn choose_action(&self, enemy: usize, party: &PartyState, rng: &mut impl Rng) -> Action {
+        let intent = self
+            .intent
+            .unwrap_or_else(|| self.choose_intent(party, rng));
+
+        Action::Enemy {
+            enemy,
+            attack: intent.attack,
+            target: self.intended_target(&intent, party),
+        }
+    }
+}
+
+// What happens next once the heroes have acted
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TurnStep {
+    Enemy(usize),
+    EndRound,
+}
+
+// Enemies still waiting to act this round, in order, then the end of the
+// round
+#[derive(Clone, Debug, Default)]
+pub struct EnemyTurn {
+    has_ended_round: bool,
+    remaining: Vec<usize>,
+}
+
+impl EnemyTurn {
+    pub fn new(enemies: &[EnemyState]) -> Self {
+        Self {
+            has_ended_round: false,
+            remaining: (0..enemies.len())
+                .filter(|enemy| enemies[*enemy].can_act())
+                .collect(),
+        }
+    }
+
+    // Skips anyone who fell or was put out of action before their go
+    pub fn next(&mut self, enemies: &[EnemyState]) -> Option<TurnStep> {
+        while !self.remaining.is_empty() {
+            let enemy = self.remaining.remove(0);
+
+            if enemies[enemy].can_act() {
+                return Some(TurnStep::Enemy(enemy));
+            }
+        }
+
+        if self.has_ended_round {
+            return None;
+        }
+
+        self.has_ended_round = true;
+
+        Some(TurnStep::EndRound)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BattleState {
+    pub enemies: Vec<EnemyState>,
+    pub party: PartyState,
+}
+
+impl BattleState {
+    pub fn new(party: PartyState, enemies: Vec<EnemyState>) -> Self {
+        Self { enemies, party }
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.enemies.iter().all(EnemyState::is_dead)
+    }
+
+    pub fn is_decided(&self) -> bool {
+        self.is_won() || self.party.is_defeated()
+    }
+
+    // A whole round in the order the game plays it out: enemies plan, the
+    // heroes act, the enemies act, then statuses tick over. Stops as soon as
+    // the battle is decided
+    pub fn run_round<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        hero_action: impl FnOnce(&mut Self, &mut R) -> Action,
+    ) -> Vec<BattleOutcome> {
+        for enemy in self.enemies.iter_mut() {
+            enemy.plan(&self.party, rng);
+        }
+
+        let action = hero_action(self, rng);
+        let mut outcomes = self.apply(&action);
+
+        if self.is_decided() {
+            return outcomes;
+        }
+
+        let mut turn = EnemyTurn::new(&self.enemies);
+
+        while let Some(step) = turn.next(&self.enemies) {
+            let action = match step {
+                TurnStep::Enemy(enemy) => {
+                    self.enemies[enemy].choose_action(enemy, &self.party, rng)
+                }
+                TurnStep::EndRound => Action::EndRound,
+            };
+
+            outcomes.extend(self.apply(&action));
+
+            if self.is_decided() {
+                break;
+            }
+        }
+
+        outcomes
+    }
+
+    pub fn apply(&mut self, action: &Action) -> Vec<BattleOutcome> {
+        let mut outcomes = Vec::new();
+
+        match *action {
+            Action::Hero {
+                hero,
+                action,
+                target,
+            } => {
+                let effects = self.party.heroes[hero].actions[action].effects.clone();
+
+                for effect in effects.iter() {
+                    self.apply_effect(hero, target, effect, &mut outcomes);
+                }
+            }
+            Action::Enemy {
+                enemy,
+                attack,
+                target,
+            } => {
+                let attack = self.enemies[enemy].attacks[attack].clone();
+
+                self.enemies[enemy].intent = None;
+
+                if attack.hits_heroes() {
+                    self.hit(
+                        Target::Enemy(enemy),
+                        Target::Hero(target),
+                        attack.damage,
+                        &mut outcomes,
+                    );
+                }
+
+                for status in attack.inflicts.iter() {
+                    self.inflict(Target::Hero(target), *status, &mut outcomes);
+                }
+
+                for status in attack.buffs.iter() {
+                    self.inflict(Target::Enemy(enemy), *status, &mut outcomes);
+                }
+            }
+            Action::Rest { .. } => {}
+            Action::EndRound => self.end_round(&mut outcomes),
+        }
+
+        if self.is_won() {
+            outcomes.push(BattleOutcome::Won);
+        } else if self.party.is_defeated() {
+            outcomes.push(BattleOutcome::Lost);
+        }
+
+        outcomes
+    }
+
+    fn apply_effect(
+        &mut self,
+        user: usize,
+        chosen: Target,
+        effect: &Effect,
+        outcomes: &mut Vec<BattleOutcome>,
+    ) {
+        let resolve = |target| match target {
+            EffectTarget::Target => chosen,
+            EffectTarget::User => Target::Hero(user),
+        };
+
+        match *effect {
+            Effect::Damage { target, amount } => {
+                self.hit(Target::Hero(user), resolve(target), amount, outcomes)
+            }
+            Effect::Heal { target, amount } => self.heal(resolve(target), amount, outcomes),
+            Effect::Status { target, status } => self.inflict(resolve(target), status, outcomes),
+        }
+    }
+
+    // The acting hero goes to sleep, then everyone's statuses take effect and
+    // run down, so a status lasting N turns acts N times
+    fn end_round(&mut self, outcomes: &mut Vec<BattleOutcome>) {
+        self.party.end_turn();
+
+        let targets = (0..self.party.heroes.len())
+            .map(Target::Hero)
+            .chain((0..self.enemies.len()).map(Target::Enemy))
+            .collect::<Vec<_>>();
+
+        for target in targets {
+            if self.is_dead(target) {
+                continue;
+            }
+
+            for effect in self.statuses(target).turn_start() {
+                match effect {
+                    TurnStartEffect::Damage(amount) => self.damage(target, amount, outcomes),
+                    TurnStartEffect::Heal(amount) => self.heal(target, amount, outcomes),
+                }
+            }
+        }
+
+        for hero in self.party.heroes.iter_mut() {
+            hero.statuses.turn_end();
+        }
+
+        for enemy in self.enemies.iter_mut() {
+            enemy.statuses.turn_end();
+        }
+
+        // Only once poison has had its say, so the last hero awake dying to it
+        // still wakes the rest
+        self.party.wake_if_all_asleep();
+
+        if !self.party.current().can_act() {
+            self.party.cycle_hero();
+        }
+    }
+
+    fn is_dead(&self, target: Target) -> bool {
+        match target {
+            Target::Enemy(enemy) => self.enemies[enemy].is_dead(),
+            Target::Hero(hero) => self.party.heroes[hero].is_dead(),
+        }
+    }
+
+    fn statuses(&self, target: Target) -> &Statuses {
+        match target {
+            Target::Enemy(enemy) => &self.enemies[enemy].statuses,
+            Target::Hero(hero) => &self.party.heroes[hero].statuses,
+        }
+    }
+
+    fn statuses_mut(&mut self, target: Target) -> &mut Statuses {
+        match target {
+            Target::Enemy(enemy) => &mut self.enemies[enemy].statuses,
+            Target::Hero(hero) => &mut self.party.heroes[hero].statuses,
+        }
+    }
+
+    // Damage from one combatant to another, after both sides' statuses
+    fn hit(
+        &mut self,
+        source: Target,
+        target: Target,
+        amount: isize,
+        outcomes: &mut Vec<BattleOutcome>,
+    ) {
+        let amount = self.statuses(source).damage_dealt(amount);
+
+        let (amount, absorbed) = self.statuses(target).damage_taken(amount);
+
+        outcomes.extend(
+            absorbed
+                .into_iter()
+                .map(|(kind, amount)| BattleOutcome::Absorbed {
+                    target,
+                    kind,
+                    amount,
+                }),
+        );
+
+        self.damage(target, amount, outcomes);
+    }
+
+    fn inflict(&mut self, target: Target, status: StatusEffect, outcomes: &mut Vec<BattleOutcome>) {
+        if self.is_dead(target) {
+            return;
+        }
+
+        self.statuses_mut(target).apply(status);
+
+        outcomes.push(BattleOutcome::StatusApplied {
+            target,
+            kind: status.kind,
+        });
+    }
+
+    fn damage(&mut self, target: Target, amount: isize, outcomes: &mut Vec<BattleOutcome>) {
+        let (hp, statuses) = match target {
+            Target::Enemy(enemy) => {
+                let enemy = &mut self.enemies[enemy];
+
+                (&mut enemy.current_hp, &mut enemy.statuses)
+            }
+            Target::Hero(hero) => {
+                let hero = &mut self.party.heroes[hero];
+
+                (&mut hero.current_hp, &mut hero.statuses)
+            }
+        };
+
+        let was_alive = *hp > 0;
+
+        *hp -= amount;
+
+        outcomes.push(BattleOutcome::Damaged { target, amount });
+
+        if was_alive && *hp <= 0 {
+            statuses.clear();
+
+            outcomes.push(BattleOutcome::Defeated { target });
+        }
+    }
+
+    fn heal(&mut self, target: Target, amount: isize, outcomes: &mut Vec<BattleOutcome>) {
+        let (hp, max_hp) = match target {
+            Target::Enemy(enemy) => {
+                let enemy = &mut self.enemies[enemy];
+
+                (&mut enemy.current_hp, enemy.max_hp)
+            }
+            Target::Hero(hero) => {
+                let hero = &mut self.party.heroes[hero];
+
+                (&mut hero.current_hp, hero.max_hp)
+            }
+        };
+
+        if *hp <= 0 {
+            outcomes.push(BattleOutcome::HealFailed { target });
+            return;
+        }
+
+        // Only what fits under the maximum counts
+        let before = *hp;
+
+        *hp = (*hp + amount).min(max_hp).max(before);
+
+        outcomes.push(BattleOutcome::Healed {
+            target,
+            amount: *hp - before,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attack() -> ActionDefinition {
+        ActionDefinition {
+            card: 0,
+            copies: 1,
+            effects: vec![Effect::Damage {
+                target: EffectTarget::Target,
+                amount: 3,
+            }],
+            name: "Attack".to_string(),
+            target: ActionTarget::Enemy,
+        }
+    }
+
+    fn bite() -> EnemyAttack {
+        EnemyAttack {
+            buffs: Vec::new(),
+            damage: 4,
+            inflicts: Vec::new(),
+            name: "Bite".to_string(),
+            target: AttackTarget::Current,
+            weight: 1,
+        }
+    }
+
+    fn battle() -> BattleState {
+        BattleState::new(
+            PartyState::new(vec![
+                HeroState::new("Warrior", 10, vec![attack()]),
+                HeroState::new("Mage", 8, vec![attack()]),
+            ]),
+            vec![EnemyState::new("Rat", 10, vec![bite()])],
+        )
+    }
+
+    const ATTACK: Action = Action::Hero {
+        hero: 0,
+        action: 0,
+        target: Target::Enemy(0),
+    };
+
+    const BITE: Action = Action::Enemy {
+        enemy: 0,
+        attack: 0,
+        target: 1,
+    };
+
+    #[test]
+    fn hero_actions_damage_the_target() {
+        let mut battle = battle();
+
+        let outcomes = battle.apply(&ATTACK);
+
+        assert_eq!(battle.enemies[0].current_hp, 7);
+        assert_eq!(
+            outcomes,
+            vec![BattleOutcome::Damaged {
+                target: Target::Enemy(0),
+                amount: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn enemy_actions_damage_the_hero() {
+        let mut battle = battle();
+
+        let outcomes = battle.apply(&BITE);
+
+        assert_eq!(battle.party.heroes[1].current_hp, 4);
+        assert_eq!(
+            outcomes,
+            vec![BattleOutcome::Damaged {
+                target: Target::Hero(1),
+                amount: 4,
+            }]
+        );
+    }
+
+    #[test]
+    fn enemies_can_defend_themselves_instead() {
+        let mut battle = battle();
+
+        battle.enemies[0].attacks[0] = EnemyAttack {
+            buffs: vec![StatusEffect::new(StatusKind::Block, 3, 2)],
+            damage: 0,
+            ..bite()
+        };
+
+        assert_eq!(battle.enemies[0].attacks[0].kind(), IntentKind::Defend);
+
+        let outcomes = battle.apply(&BITE);
+
+        assert_eq!(battle.party.heroes[1].current_hp, 8);
+        assert!(battle.enemies[0].statuses.has(StatusKind::Block));
+        assert_eq!(
+            outcomes,
+            vec![BattleOutcome::StatusApplied {
+                target: Target::Enemy(0),
+                kind: StatusKind::Block,
+            }]
+        );
+    }
+
+    #[test]
+    fn ending_the_round_rests_the_hero_that_acted() {
+        let mut battle = battle();
+
+        battle.apply(&ATTACK);
+        let outcomes = battle.apply(&Action::EndRound);
+
+        assert!(outcomes.is_empty());
+        assert!(battle.party.heroes[0].statuses.has(StatusKind::Sleep));
+        assert_eq!(battle.party.current_hero, 1);
+    }
+
+    #[test]
+    fn statuses_act_once_for_every_turn_they_last() {
+        let mut battle = battle();
+
+        battle.enemies[0]
+            .statuses
+            .apply(StatusEffect::new(StatusKind::Poison, 1, 3));
+
+        let ticks = (0..5)
+            .flat_map(|_| battle.apply(&Action::EndRound))
+            .filter(|outcome| {
+                *outcome
+                    == BattleOutcome::Damaged {
+                        target: Target::Enemy(0),
+                        amount: 1,
+                    }
+            })
+            .count();
+
+        assert_eq!(ticks, 3);
+        assert_eq!(battle.enemies[0].current_hp, 7);
+        assert!(!battle.enemies[0].statuses.has(StatusKind::Poison));
+    }
+
+    #[test]
+    fn the_party_wakes_when_poison_kills_the_last_hero_awake() {
+        let mut battle = BattleState::new(
+            PartyState::new(vec![
+                HeroState::new("Warrior", 10, vec![attack()]),
+                HeroState::new("Mage", 8, vec![attack()]),
+                HeroState::new("Cleric", 8, vec![attack()]),
+            ]),
+            vec![EnemyState::new("Rat", 10, vec![bite()])],
+        );
+
+        battle.party.heroes[1]
+            .statuses
+            .apply(StatusEffect::new(StatusKind::Sleep, 0, 0));
+        battle.party.heroes[2].current_hp = 1;
+        battle.party.heroes[2]
+            .statuses
+            .apply(StatusEffect::new(StatusKind::Poison, 1, 3));
+
+        battle.apply(&ATTACK);
+        battle.apply(&Action::EndRound);
+
+        assert!(battle.party.heroes[2].is_dead());
+        assert!(battle.party.current().can_act());
+    }
+
+    #[test]
+    fn healing_reports_only_what_fits() {
+        let mut battle = battle();
+
+        battle.party.heroes[1].actions.push(ActionDefinition {
+            card: 0,
+            copies: 1,
+            effects: vec![Effect::Heal {
+                target: EffectTarget::Target,
+                amount: 5,
+            }],
+            name: "Heal".to_string(),
+            target: ActionTarget::Ally,
+        });
+        battle.party.heroes[0].current_hp = 8;
+
+        let outcomes = battle.apply(&Action::Hero {
+            hero: 1,
+            action: 1,
+            target: Target::Hero(0),
+        });
+
+        assert_eq!(battle.party.heroes[0].current_hp, 10);
+        assert_eq!(
+            outcomes,
+            vec![BattleOutcome::Healed {
+                target: Target::Hero(0),
+                amount: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn defeating_every_enemy_wins() {
+        let mut battle = battle();
+
+        battle.enemies[0].current_hp = 3;
+
+        let outcomes = battle.apply(&ATTACK);
+
+        assert!(battle.is_won());
+        assert_eq!(
+            outcomes[1..],
+            [
+                BattleOutcome::Defeated {
+                    target: Target::Enemy(0),
+                },
+                BattleOutcome::Won,
+            ]
+        );
+    }
+
+    #[test]
+    fn losing_every_hero_loses() {
+        let mut battle = battle();
+
+        battle.party.heroes[0].current_hp = 0;
+        battle.party.heroes[1].current_hp = 4;
+
+        let outcomes = battle.apply(&BITE);
+
+        assert!(battle.party.is_defeated());
+        assert_eq!(outcomes.last(), Some(&BattleOutcome::Lost));
+    }
+}
@@ -0,0 +1,342 @@
+use bevy::{input::mouse::MouseWheel, prelude::*, sprite::Anchor, text::Text2dBounds};
+
+use crate::{
+    input::{GameInputSet, InputAction, InputActions, OptionsState},
+    tower::FloorCleared,
+    AppState, GAME_HEIGHT, GAME_WIDTH, UNIT_SIZE,
+};
+
+const LOG_FONT_SIZE: f32 = 8.0;
+const LOG_TEXT_SIZE: Vec2 = Vec2::new(
+    GAME_WIDTH * 0.75 - UNIT_SIZE,
+    GAME_HEIGHT * 0.75 - UNIT_SIZE * 1.5,
+);
+
+// The font is square and monospaced, so the panel fits a fixed number of
+// characters to a line and lines to the page. Whatever doesn't fit is a
+// scroll away
+const LOG_COLUMNS: usize = (LOG_TEXT_SIZE.x / LOG_FONT_SIZE) as usize;
+const LOG_ROWS: usize = (LOG_TEXT_SIZE.y / LOG_FONT_SIZE) as usize;
+
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    // Already wrapped to the panel
+    pub lines: Vec<String>,
+    pub message: String,
+    pub turn: usize,
+}
+
+// Breaks at spaces where it can, and mid-word where a word is too long
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word = word.chars().collect::<Vec<_>>();
+
+        if !line.is_empty() && line.chars().count() + 1 + word.len() > columns {
+            lines.push(std::mem::take(&mut line));
+        }
+
+        while word.len() > columns {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+
+            lines.push(word.drain(..columns).collect());
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+
+        line.extend(word);
+    }
+
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+// Every message of the current fight, oldest first
+#[derive(Debug, Default, Resource)]
+pub struct BattleLog {
+    pub entries: Vec<LogEntry>,
+    round: usize,
+}
+
+impl BattleLog {
+    pub fn push(&mut self, message: String) {
+        let turn = self.round + 1;
+
+        self.entries.push(LogEntry {
+            lines: wrap(&format!("Turn {turn}: {message}"), LOG_COLUMNS),
+            message,
+            turn,
+        });
+    }
+
+    pub fn end_round(&mut self) {
+        self.round += 1;
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, States)]
+pub enum LogState {
+    #[default]
+    Closed,
+    Open,
+}
+
+// How many entries back from the newest the panel is showing
+#[derive(Default, Resource)]
+struct LogScroll(usize);
+
+#[derive(Component)]
+struct LogNode;
+
+#[derive(Component)]
+struct LogText;
+
+#[derive(Component)]
+struct LogHint;
+
+fn clear_log(mut battle_log: ResMut<BattleLog>) {
+    *battle_log = BattleLog::default();
+}
+
+fn toggle_log(
+    input_actions: Res<InputActions>,
+    mut next_state: ResMut<NextState<LogState>>,
+    state: Res<State<LogState>>,
+) {
+    next_state.set(match state.get() {
+        LogState::Closed if input_actions.just_pressed(InputAction::Log) => LogState::Open,
+        LogState::Open
+            if input_actions.just_pressed(InputAction::Log)
+                || input_actions.just_triggered(InputAction::Cancel) =>
+        {
+            LogState::Closed
+        }
+        _ => return,
+    });
+}
+
+fn close_log(mut next_state: ResMut<NextState<LogState>>) {
+    next_state.set(LogState::Closed);
+}
+
+fn setup_log(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    mut scroll: ResMut<LogScroll>,
+) {
+    let font_handle = asset_server.load("fonts/press_start_2p.ttf");
+
+    scroll.0 = 0;
+
+    let left = -(GAME_WIDTH * 0.375) + UNIT_SIZE * 0.5;
+    let top = GAME_HEIGHT * 0.375 - UNIT_SIZE * 0.5;
+
+    commands
+        .spawn((
+            LogNode,
+            SpatialBundle {
+                transform: Transform {
+                    translation: Vec3::new(0.0, 0.0, 10.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            // Background
+
+            parent.spawn((
+                ImageScaleMode::Sliced(TextureSlicer {
+                    border: BorderRect::square(4.0),
+                    center_scale_mode: SliceScaleMode::Stretch,
+                    sides_scale_mode: SliceScaleMode::Stretch,
+                    max_corner_scale: 2.0,
+                }),
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(GAME_WIDTH * 0.75, GAME_HEIGHT * 0.75)),
+                        ..default()
+                    },
+                    texture: asset_server.load("basic_background.png"),
+                    transform: Transform {
+                        translation: Vec3::new(0.0, 0.0, -1.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                LogText,
+                Text2dBundle {
+                    text: Text {
+                        linebreak_behavior: bevy::text::BreakLineOn::WordBoundary,
+                        justify: JustifyText::Left,
+                        sections: vec![TextSection::new(
+                            "",
+                            TextStyle {
+                                font: font_handle.clone(),
+                                font_size: LOG_FONT_SIZE,
+                                ..default()
+                            },
+                        )],
+                    },
+                    text_2d_bounds: Text2dBounds {
+                        size: LOG_TEXT_SIZE,
+                    },
+                    text_anchor: Anchor::TopLeft,
+                    transform: Transform {
+                        translation: Vec3::new(left, top, 0.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                LogHint,
+                Text2dBundle {
+                    text: Text::from_section(
+                        "LogHint",
+                        TextStyle {
+                            font: font_handle.clone(),
+                            font_size: 8.0,
+                            ..default()
+                        },
+                    ),
+                    text_anchor: Anchor::BottomLeft,
+                    transform: Transform {
+                        translation: Vec3::new(left, -top, 0.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn cleanup_log(mut commands: Commands, node_query: Query<Entity, With<LogNode>>) {
+    for entity_id in node_query.iter() {
+        commands.entity(entity_id).despawn_recursive();
+    }
+}
+
+fn log_input(
+    battle_log: Res<BattleLog>,
+    input_actions: Res<InputActions>,
+    mut mouse_wheel_reader: EventReader<MouseWheel>,
+    mut scroll: ResMut<LogScroll>,
+) {
+    let wheel = mouse_wheel_reader.read().map(|event| event.y).sum::<f32>();
+
+    if input_actions.just_pressed(InputAction::Up) || wheel > 0.0 {
+        scroll.0 += 1;
+    }
+
+    if input_actions.just_pressed(InputAction::Down) || wheel < 0.0 {
+        scroll.0 = scroll.0.saturating_sub(1);
+    }
+
+    // Stop once the first entry is at the top
+    let mut rows = 0;
+    let fits = battle_log
+        .entries
+        .iter()
+        .take_while(|entry| {
+            rows += entry.lines.len();
+            rows <= LOG_ROWS
+        })
+        .count()
+        .max(1);
+
+    scroll.0 = scroll.0.min(battle_log.entries.len().saturating_sub(fits));
+}
+
+// Where the page ending just before `end` starts, as far back as fits
+fn page_start(entries: &[LogEntry], end: usize) -> usize {
+    let mut rows = 0;
+    let mut start = end;
+
+    while start > 0 {
+        rows += entries[start - 1].lines.len();
+
+        // The newest entry shows even if it is taller than the page
+        if rows > LOG_ROWS && start < end {
+            break;
+        }
+
+        start -= 1;
+    }
+
+    start
+}
+
+fn log_text(
+    battle_log: Res<BattleLog>,
+    mut hint_query: Query<&mut Text, (With<LogHint>, Without<LogText>)>,
+    scroll: Res<LogScroll>,
+    mut text_query: Query<&mut Text, With<LogText>>,
+) {
+    let end = battle_log.entries.len() - scroll.0;
+    let start = page_start(&battle_log.entries, end);
+
+    let lines = battle_log.entries[start..end]
+        .iter()
+        .flat_map(|entry| entry.lines.iter().map(String::as_str))
+        .collect::<Vec<_>>();
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = lines[lines.len().saturating_sub(LOG_ROWS)..].join("\n");
+    }
+
+    for mut text in hint_query.iter_mut() {
+        text.sections[0].value = if battle_log.entries.is_empty() {
+            "Nothing has happened yet".to_string()
+        } else {
+            format!(
+                "{}-{} of {}, Up/Down to scroll",
+                start + 1,
+                end,
+                battle_log.entries.len()
+            )
+        };
+    }
+}
+
+pub struct BattleLogPlugin;
+
+impl Plugin for BattleLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BattleLog>()
+            .init_resource::<LogScroll>()
+            .init_state::<LogState>()
+            .configure_sets(Update, GameInputSet.run_if(in_state(LogState::Closed)))
+            .add_systems(OnEnter(AppState::Run), clear_log)
+            .add_systems(OnExit(AppState::Run), close_log)
+            .add_systems(OnEnter(OptionsState::Open), close_log)
+            .add_systems(OnEnter(LogState::Open), setup_log)
+            .add_systems(OnExit(LogState::Open), cleanup_log)
+            .add_systems(
+                Update,
+                (
+                    (
+                        clear_log.run_if(on_event::<FloorCleared>()),
+                        toggle_log.run_if(in_state(OptionsState::Closed)),
+                    )
+                        .run_if(in_state(AppState::Run)),
+                    (log_input, log_text)
+                        .chain()
+                        .run_if(in_state(LogState::Open)),
+                ),
+            );
+    }
+}
@@ -0,0 +1,344 @@
+// Plays whole runs up the tower without a window, so balance changes to the
+// hero, enemy and tower files can be judged over thousands of games.
+//
+//     cargo run --release --bin simulate -- --runs 5000 --policy scripted
+
+use std::path::{Path, PathBuf};
+
+use bevy_prng::WyRand;
+use cycle_the_heroes::{
+    battle::{
+        Action, ActionDefinition, ActionTarget, BattleState, Card, DeckState, Effect, EffectTarget,
+        EnemyState, HeroState, PartyState, Target, HAND_SIZE,
+    },
+    enemy::{EnemyDefinitionFile, EnemyLoaderError},
+    hero::{HeroDefinition, PartyDefinitionFile},
+    tower::{TowerDefinitionFile, TowerLoaderError},
+};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+// A battle nobody can finish counts as a loss
+const MAX_TURNS: usize = 500;
+
+#[derive(Debug, Error)]
+enum SimulateError {
+    #[error("Could not read {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Could not parse {0}: {1}")]
+    Ron(PathBuf, ron::error::SpannedError),
+    #[error("Invalid enemy {0}: {1}")]
+    Enemy(PathBuf, EnemyLoaderError),
+    #[error("Invalid tower {0}: {1}")]
+    Tower(PathBuf, TowerLoaderError),
+    #[error("Invalid value for {0}: {1}")]
+    Argument(&'static str, String),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Policy {
+    // Any playable card at any target
+    Random,
+    // Heals whoever is hurt worst, otherwise hits the weakest enemy hardest
+    Scripted,
+}
+
+struct Game {
+    floors: Vec<Vec<EnemyState>>,
+    heroes: Vec<HeroState>,
+}
+
+impl Game {
+    fn load(assets: &Path) -> Result<Self, SimulateError> {
+        let party = read::<PartyDefinitionFile>(&assets.join("party.ron"))?;
+        let tower_path = assets.join("tower.ron");
+        let tower = read::<TowerDefinitionFile>(&tower_path)?;
+
+        // The same checks the game's loaders make
+        tower
+            .validate()
+            .map_err(|error| SimulateError::Tower(tower_path, error))?;
+
+        let heroes = party
+            .heroes
+            .iter()
+            .map(|path| {
+                read::<HeroDefinition>(&assets.join(path))
+                    .map(|hero| HeroState::new(&hero.name, hero.max_hp, hero.actions))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let floors = tower
+            .floors
+            .iter()
+            .map(|floor| {
+                floor
+                    .iter()
+                    .map(|path| {
+                        let path = assets.join(path);
+                        let enemy = read::<EnemyDefinitionFile>(&path)?;
+
+                        enemy
+                            .validate()
+                            .map_err(|error| SimulateError::Enemy(path, error))?;
+
+                        Ok(EnemyState::new(&enemy.name, enemy.max_hp, enemy.attacks))
+                    })
+                    .collect::<Result<_, _>>()
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { floors, heroes })
+    }
+}
+
+#[derive(Default)]
+struct RunResult {
+    floors_cleared: usize,
+    hp: Vec<isize>,
+    is_won: bool,
+    turns: usize,
+}
+
+fn read<T: DeserializeOwned>(path: &Path) -> Result<T, SimulateError> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|error| SimulateError::Io(path.into(), error))?;
+
+    ron::from_str(&contents).map_err(|error| SimulateError::Ron(path.into(), error))
+}
+
+fn arg(name: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != name).nth(1)
+}
+
+fn parsed_arg<T: std::str::FromStr>(name: &'static str, default: T) -> Result<T, SimulateError> {
+    match arg(name) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| SimulateError::Argument(name, value)),
+        None => Ok(default),
+    }
+}
+
+// Everyone the card could be aimed at, the same as the target picker offers
+fn candidates(battle: &BattleState, card: &Card) -> Vec<Target> {
+    match battle.party.heroes[card.hero].actions[card.action].target {
+        ActionTarget::Ally => (0..battle.party.heroes.len())
+            .filter(|hero| !battle.party.heroes[*hero].is_dead())
+            .map(Target::Hero)
+            .collect(),
+        ActionTarget::Enemy => (0..battle.enemies.len())
+            .filter(|enemy| !battle.enemies[*enemy].is_dead())
+            .map(Target::Enemy)
+            .collect(),
+        ActionTarget::User => vec![Target::Hero(card.hero)],
+    }
+}
+
+fn total(definition: &ActionDefinition, is_heal: bool) -> isize {
+    definition
+        .effects
+        .iter()
+        .map(|effect| match *effect {
+            Effect::Damage {
+                target: EffectTarget::Target,
+                amount,
+            } if !is_heal => amount,
+            Effect::Heal {
+                target: EffectTarget::Target,
+                amount,
+            } if is_heal => amount,
+            _ => 0,
+        })
+        .sum()
+}
+
+fn choose_random(battle: &BattleState, playable: &[Card], rng: &mut impl Rng) -> Option<Action> {
+    let card = playable.choose(rng)?;
+    let target = *candidates(battle, card).choose(rng)?;
+
+    Some(Action::Hero {
+        hero: card.hero,
+        action: card.action,
+        target,
+    })
+}
+
+fn choose_scripted(battle: &BattleState, playable: &[Card]) -> Option<Action> {
+    let action = |card: &Card, target: Target| Action::Hero {
+        hero: card.hero,
+        action: card.action,
+        target,
+    };
+    let definition = |card: &Card| &battle.party.heroes[card.hero].actions[card.action];
+
+    // Patch up anyone under half health first
+    let hurt = (0..battle.party.heroes.len())
+        .filter(|hero| {
+            let state = &battle.party.heroes[*hero];
+
+            !state.is_dead() && state.current_hp * 2 < state.max_hp
+        })
+        .min_by_key(|hero| battle.party.heroes[*hero].current_hp);
+
+    if let Some(hero) = hurt {
+        let heal = playable
+            .iter()
+            .filter(|card| total(definition(card), true) > 0)
+            .max_by_key(|card| total(definition(card), true));
+
+        if let Some(card) = heal {
+            return Some(action(card, Target::Hero(hero)));
+        }
+    }
+
+    let weakest = (0..battle.enemies.len())
+        .filter(|enemy| !battle.enemies[*enemy].is_dead())
+        .min_by_key(|enemy| battle.enemies[*enemy].current_hp)?;
+
+    let attack = playable
+        .iter()
+        .filter(|card| total(definition(card), false) > 0)
+        .max_by_key(|card| total(definition(card), false));
+
+    if let Some(card) = attack {
+        return Some(action(card, Target::Enemy(weakest)));
+    }
+
+    // Nothing better to do, so play whatever is left at its first target
+    playable
+        .iter()
+        .find_map(|card| Some(action(card, *candidates(battle, card).first()?)))
+}
+
+// Plays one battle a round at a time, returning whether it was won
+fn fight(
+    battle: &mut BattleState,
+    deck: &mut DeckState,
+    policy: Policy,
+    rng: &mut impl Rng,
+    turns: &mut usize,
+) -> bool {
+    for _ in 0..MAX_TURNS {
+        *turns += 1;
+
+        battle.run_round(rng, |battle, rng| {
+            deck.draw_hand(HAND_SIZE, &battle.party, rng);
+
+            let playable = deck
+                .hand
+                .iter()
+                .filter(|card| card.is_playable(&battle.party))
+                .copied()
+                .collect::<Vec<_>>();
+
+            let action = match policy {
+                Policy::Random => choose_random(battle, &playable, rng),
+                Policy::Scripted => choose_scripted(battle, &playable),
+            }
+            .unwrap_or(Action::Rest {
+                hero: battle.party.current_hero,
+            });
+
+            if let Action::Hero { hero, action, .. } = action {
+                battle.party.select_hero(hero);
+                deck.play(hero, action);
+            }
+
+            action
+        });
+
+        if battle.is_decided() {
+            return battle.is_won();
+        }
+    }
+
+    false
+}
+
+fn simulate_run(game: &Game, policy: Policy, rng: &mut impl Rng) -> RunResult {
+    let mut party = PartyState::new(game.heroes.clone());
+    let mut deck = DeckState::new(&party, rng);
+    let mut result = RunResult::default();
+
+    for enemies in game.floors.iter() {
+        party.start_battle();
+
+        let mut battle = BattleState::new(party, enemies.clone());
+        let is_won = fight(&mut battle, &mut deck, policy, rng, &mut result.turns);
+
+        party = battle.party;
+
+        if !is_won {
+            break;
+        }
+
+        result.floors_cleared += 1;
+    }
+
+    result.hp = party
+        .heroes
+        .iter()
+        .map(|hero| hero.current_hp.max(0))
+        .collect();
+    result.is_won = result.floors_cleared == game.floors.len();
+
+    result
+}
+
+fn run() -> Result<(), SimulateError> {
+    let assets = arg("--assets")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("assets"));
+    let runs = parsed_arg("--runs", 1000usize)?.max(1);
+    let seed = parsed_arg("--seed", rand::random::<u64>())?;
+    let policy = match arg("--policy").as_deref() {
+        None | Some("random") => Policy::Random,
+        Some("scripted") => Policy::Scripted,
+        Some(other) => return Err(SimulateError::Argument("--policy", other.to_string())),
+    };
+
+    let game = Game::load(&assets)?;
+    let mut rng = WyRand::seed_from_u64(seed);
+
+    let results = (0..runs)
+        .map(|_| simulate_run(&game, policy, &mut rng))
+        .collect::<Vec<_>>();
+
+    let runs = runs as f32;
+    let wins = results.iter().filter(|result| result.is_won).count();
+    let turns = results.iter().map(|result| result.turns).sum::<usize>();
+
+    println!("{runs} runs, {policy:?} policy, seed {seed}");
+    println!("Win rate:      {:.1}%", wins as f32 / runs * 100.0);
+    println!("Average turns: {:.1}", turns as f32 / runs);
+
+    println!("Floors cleared:");
+
+    for floor in 0..game.floors.len() {
+        let cleared = results
+            .iter()
+            .filter(|result| result.floors_cleared > floor)
+            .count();
+
+        println!("  {}: {:.1}%", floor + 1, cleared as f32 / runs * 100.0);
+    }
+
+    println!("Average HP remaining:");
+
+    for (i, hero) in game.heroes.iter().enumerate() {
+        let hp = results.iter().map(|result| result.hp[i]).sum::<isize>();
+
+        println!("  {:<10}{:.1}/{}", hero.name, hp as f32 / runs, hero.max_hp);
+    }
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,136 @@
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+        texture::BevyDefault,
+        view::RenderLayers,
+    },
+    window::{PrimaryWindow, WindowMode},
+};
+
+use crate::{
+    input::{InputAction, InputActions},
+    GAME_HEIGHT, GAME_WIDTH,
+};
+
+// Only the window camera sees this layer, everything else is drawn by the
+// canvas camera at native resolution
+const SCREEN_LAYER: RenderLayers = RenderLayers::layer(1);
+
+const LETTERBOX_COLOR: Color = Color::BLACK;
+
+// The camera looking at the window, which maps the cursor back into the game
+#[derive(Component)]
+pub struct ScreenCamera;
+
+// The largest whole multiple of the native resolution that fits the window,
+// shrinking to fit when the window is smaller than that
+fn canvas_scale(width: f32, height: f32) -> f32 {
+    let fit = (width / GAME_WIDTH).min(height / GAME_HEIGHT);
+
+    if fit >= 1.0 {
+        fit.floor()
+    } else {
+        fit
+    }
+}
+
+fn setup_cameras(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let size = Extent3d {
+        height: GAME_HEIGHT as u32,
+        width: GAME_WIDTH as u32,
+        ..default()
+    };
+
+    let mut render_target = Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::bevy_default(),
+        default(),
+    );
+
+    render_target.texture_descriptor.usage =
+        TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST;
+
+    let render_target_handle = images.add(render_target);
+
+    // Draws the game into the canvas, one world unit to a pixel
+
+    commands.spawn(Camera2dBundle {
+        camera: Camera {
+            order: -1,
+            target: RenderTarget::Image(render_target_handle.clone()),
+            ..default()
+        },
+        ..default()
+    });
+
+    // Draws the canvas into the window, scaled up and letterboxed
+
+    commands.spawn((
+        SCREEN_LAYER,
+        SpriteBundle {
+            texture: render_target_handle,
+            ..default()
+        },
+    ));
+
+    commands.spawn((
+        ScreenCamera,
+        SCREEN_LAYER,
+        Camera2dBundle {
+            camera: Camera {
+                clear_color: ClearColorConfig::Custom(LETTERBOX_COLOR),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+}
+
+fn fit_canvas(
+    mut projection_query: Query<&mut OrthographicProjection, With<ScreenCamera>>,
+    window_query: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+
+    // Minimised windows have no size at all
+    if window.width() <= 0.0 || window.height() <= 0.0 {
+        return;
+    }
+
+    let scale = canvas_scale(window.width(), window.height());
+
+    for mut projection in projection_query.iter_mut() {
+        projection.scale = scale.recip();
+    }
+}
+
+fn toggle_fullscreen(
+    input_actions: Res<InputActions>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !input_actions.just_pressed(InputAction::Fullscreen) {
+        return;
+    }
+
+    for mut window in window_query.iter_mut() {
+        window.mode = match window.mode {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        };
+    }
+}
+
+pub struct CanvasPlugin;
+
+impl Plugin for CanvasPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_cameras)
+            .add_systems(Update, (fit_canvas, toggle_fullscreen));
+    }
+}
@@ -0,0 +1,412 @@
+use bevy::prelude::*;
+use bevy_prng::WyRand;
+use bevy_rand::prelude::{EntropyComponent, ForkableRng, GlobalEntropy};
+
+use crate::{
+    battle::{Action, ActionTarget, DeckState, Target, HAND_SIZE},
+    hero::{HeroCycled, Player},
+    input::{GameInputSet, InputAction, InputActions},
+    mouse::MousePosition,
+    target::TargetSelection,
+    ActionEvent, AppState, BattleState, InfoPanelNode, InfoPanelState, InfoPanelTarget, MenuState,
+    CARD_COL_WIDTH, CARD_SIZE, UNIT_SIZE,
+};
+
+// The slot after the hand is always there, and rests the current hero
+const REST_SLOT: usize = HAND_SIZE;
+const REST_CARD_INDEX: usize = 2;
+
+const UNPLAYABLE_COLOR: Color = Color::srgb(0.4, 0.4, 0.4);
+
+#[derive(Resource)]
+pub struct Deck {
+    pub rng: EntropyComponent<WyRand>,
+    pub state: DeckState,
+}
+
+#[derive(Default, Resource)]
+pub struct HandSelection(pub usize);
+
+#[derive(Resource)]
+struct CardsAtlas {
+    layout: Handle<TextureAtlasLayout>,
+    texture: Handle<Image>,
+}
+
+#[derive(Component)]
+struct CardSlot(usize);
+
+#[derive(Component)]
+struct CardFrame(usize);
+
+#[derive(Component)]
+struct CardIcon(usize);
+
+// Who plays the selected card and what it is, too long to fit on the card
+#[derive(Component)]
+struct CardCaption;
+
+#[derive(Component)]
+struct CardCursor;
+
+fn slot_offset(slot: usize) -> f32 {
+    (slot as f32 - HAND_SIZE as f32 * 0.5) * CARD_COL_WIDTH
+}
+
+fn load_cards(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let layout = TextureAtlasLayout::from_grid(UVec2::splat(32), 2, 2, None, None);
+
+    commands.insert_resource(CardsAtlas {
+        layout: texture_atlas_layouts.add(layout),
+        texture: asset_server.load("cards.png"),
+    });
+}
+
+pub fn insert_deck(
+    mut commands: Commands,
+    mut global_entropy: ResMut<GlobalEntropy<WyRand>>,
+    player: Res<Player>,
+) {
+    let mut rng = global_entropy.fork_rng();
+    let state = DeckState::new(&player, &mut rng);
+
+    commands.insert_resource(Deck { rng, state });
+}
+
+pub fn draw_hand(mut deck: ResMut<Deck>, player: Res<Player>) {
+    let Deck { rng, state } = deck.as_mut();
+
+    state.draw_hand(HAND_SIZE, &player, rng);
+}
+
+fn play_cards(mut action_event_reader: EventReader<ActionEvent>, mut deck: ResMut<Deck>) {
+    for event in action_event_reader.read() {
+        if let Action::Hero { hero, action, .. } = **event {
+            deck.state.play(hero, action);
+        }
+    }
+}
+
+fn setup_hand(
+    asset_server: Res<AssetServer>,
+    cards_atlas: Res<CardsAtlas>,
+    mut commands: Commands,
+    target_query: Query<Entity, With<InfoPanelTarget>>,
+) {
+    let font_handle = asset_server.load("fonts/press_start_2p.ttf");
+    let frame_texture_handle = asset_server.load("basic_background.png");
+    let scale_mode = ImageScaleMode::Sliced(TextureSlicer {
+        border: BorderRect::square(4.0),
+        center_scale_mode: SliceScaleMode::Stretch,
+        sides_scale_mode: SliceScaleMode::Stretch,
+        max_corner_scale: 2.0,
+    });
+
+    let Ok(target_entity_id) = target_query.get_single() else {
+        return;
+    };
+
+    let text_style = TextStyle {
+        font: font_handle.clone(),
+        font_size: 8.0,
+        ..default()
+    };
+
+    commands.entity(target_entity_id).with_children(|parent| {
+        parent
+            .spawn((InfoPanelNode, SpatialBundle::default()))
+            .with_children(|parent| {
+                // Cursor
+
+                parent.spawn((
+                    CardCursor,
+                    scale_mode.clone(),
+                    SpriteBundle {
+                        sprite: Sprite {
+                            custom_size: Some(CARD_SIZE + Vec2::splat(8.0)),
+                            ..default()
+                        },
+                        texture: asset_server.load("card_target.png"),
+                        transform: Transform {
+                            translation: Vec3::new(0.0, 0.0, -0.5),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ));
+
+                // Cards
+
+                for i in 0..=HAND_SIZE {
+                    parent
+                        .spawn((
+                            CardSlot(i),
+                            SpatialBundle {
+                                transform: Transform {
+                                    translation: Vec3::new(slot_offset(i), 0.0, 0.0),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                CardFrame(i),
+                                scale_mode.clone(),
+                                SpriteBundle {
+                                    sprite: Sprite {
+                                        custom_size: Some(CARD_SIZE),
+                                        ..default()
+                                    },
+                                    texture: frame_texture_handle.clone(),
+                                    ..default()
+                                },
+                            ));
+
+                            parent.spawn((
+                                CardIcon(i),
+                                SpriteBundle {
+                                    texture: cards_atlas.texture.clone(),
+                                    transform: Transform {
+                                        translation: Vec3::new(0.0, 0.0, 0.1),
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                TextureAtlas {
+                                    index: 0,
+                                    layout: cards_atlas.layout.clone(),
+                                },
+                            ));
+                        });
+                }
+
+                parent.spawn((
+                    CardCaption,
+                    Text2dBundle {
+                        text: Text::from_section("CardCaption", text_style),
+                        transform: Transform {
+                            translation: Vec3::new(0.0, (CARD_SIZE.y + UNIT_SIZE) * 0.5, 0.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ));
+            });
+    });
+}
+
+fn hand_cards(
+    mut caption_query: Query<&mut Text, With<CardCaption>>,
+    deck: Res<Deck>,
+    mut frame_query: Query<(&CardFrame, &mut Sprite), Without<CardIcon>>,
+    hand_selection: Res<HandSelection>,
+    mut icon_query: Query<(&CardIcon, &mut Sprite, &mut TextureAtlas), Without<CardFrame>>,
+    player: Res<Player>,
+    mut slot_query: Query<(&CardSlot, &mut Visibility)>,
+) {
+    let hand = &deck.state.hand;
+
+    for (slot, mut visibility) in slot_query.iter_mut() {
+        *visibility = if slot.0 == REST_SLOT || slot.0 < hand.len() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    // The rest slot shows who would be resting
+    let card_hero = |slot: usize| match hand.get(slot) {
+        Some(card) => card.hero,
+        None => player.current_hero,
+    };
+
+    let color = |slot: usize| match hand.get(slot) {
+        Some(card) if !card.is_playable(&player) => UNPLAYABLE_COLOR,
+        _ => Color::WHITE,
+    };
+
+    for (frame, mut sprite) in frame_query.iter_mut() {
+        sprite.color = color(frame.0);
+    }
+
+    for (icon, mut sprite, mut texture_atlas) in icon_query.iter_mut() {
+        sprite.color = color(icon.0);
+        texture_atlas.index = match hand.get(icon.0) {
+            Some(card) => player.heroes[card.hero].actions[card.action].card,
+            None => REST_CARD_INDEX,
+        };
+    }
+
+    let Some(hero) = player.heroes.get(card_hero(hand_selection.0)) else {
+        return;
+    };
+
+    let name = match hand.get(hand_selection.0) {
+        Some(card) => hero.actions[card.action].name.as_str(),
+        None => "Rest",
+    };
+
+    for mut text in caption_query.iter_mut() {
+        text.sections[0].value = format!("{}: {}", hero.name, name);
+    }
+}
+
+// The card under the mouse, skipping empty slots
+fn hovered_slot(
+    hand_len: usize,
+    mouse_position: &MousePosition,
+    slot_query: &Query<(&CardSlot, &GlobalTransform)>,
+) -> Option<usize> {
+    slot_query
+        .iter()
+        .filter(|(slot, _)| slot.0 == REST_SLOT || slot.0 < hand_len)
+        .find(|(_, transform)| {
+            mouse_position.is_over(transform.translation().truncate(), CARD_SIZE)
+        })
+        .map(|(slot, _)| slot.0)
+}
+
+fn hand_cursor(
+    mut cursor_query: Query<&mut Transform, With<CardCursor>>,
+    hand_selection: Res<HandSelection>,
+) {
+    for mut transform in cursor_query.iter_mut() {
+        transform.translation.x = slot_offset(hand_selection.0);
+    }
+}
+
+fn hand_cursor_change(
+    mut cursor_moved_reader: EventReader<CursorMoved>,
+    deck: Res<Deck>,
+    mut hand_selection: ResMut<HandSelection>,
+    input_actions: Res<InputActions>,
+    mouse_position: Res<MousePosition>,
+    slot_query: Query<(&CardSlot, &GlobalTransform)>,
+) {
+    // Cards in hand, then rest
+    let mut slots = (0..deck.state.hand.len()).collect::<Vec<_>>();
+
+    slots.push(REST_SLOT);
+
+    let mut position = slots
+        .iter()
+        .position(|slot| *slot == hand_selection.0)
+        .unwrap_or(slots.len() - 1);
+
+    if input_actions.just_pressed(InputAction::Right)
+        || input_actions.just_pressed(InputAction::Down)
+    {
+        position = (position + 1) % slots.len();
+    }
+
+    if input_actions.just_pressed(InputAction::Left) || input_actions.just_pressed(InputAction::Up)
+    {
+        position = (position + slots.len() - 1) % slots.len();
+    }
+
+    hand_selection.0 = slots[position];
+
+    // Only follow the mouse when it moves, so it doesn't fight the keys
+    if cursor_moved_reader.read().count() > 0 {
+        if let Some(hovered) = hovered_slot(deck.state.hand.len(), &mouse_position, &slot_query) {
+            hand_selection.0 = hovered;
+        }
+    }
+}
+
+fn hand_select(
+    mut action_event_writer: EventWriter<ActionEvent>,
+    deck: Res<Deck>,
+    mut hand_selection: ResMut<HandSelection>,
+    mut hero_cycled_writer: EventWriter<HeroCycled>,
+    input_actions: Res<InputActions>,
+    mouse_position: Res<MousePosition>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut next_state: ResMut<NextState<InfoPanelState>>,
+    mut player: ResMut<Player>,
+    slot_query: Query<(&CardSlot, &GlobalTransform)>,
+    mut target_selection: ResMut<TargetSelection>,
+) {
+    // Picks who would rest
+    if input_actions.just_pressed(InputAction::Cycle) {
+        let current_hero = player.current_hero;
+
+        player.cycle_hero();
+
+        if player.current_hero != current_hero {
+            hero_cycled_writer.send(HeroCycled);
+        }
+        return;
+    }
+
+    let mut is_selected = input_actions.just_pressed(InputAction::Confirm);
+
+    // Clicking a card plays it straight away
+    if input_actions.just_clicked(InputAction::Confirm) {
+        if let Some(hovered) = hovered_slot(deck.state.hand.len(), &mouse_position, &slot_query) {
+            hand_selection.0 = hovered;
+            is_selected = true;
+        }
+    }
+
+    if !is_selected {
+        return;
+    }
+
+    let Some(card) = deck.state.hand.get(hand_selection.0).copied() else {
+        next_state.set(InfoPanelState::Battle);
+        action_event_writer.send(ActionEvent(Action::Rest {
+            hero: player.current_hero,
+        }));
+        return;
+    };
+
+    if !card.is_playable(&player) {
+        return;
+    }
+
+    player.select_hero(card.hero);
+
+    // Pick who it goes to first
+    if player.current().actions[card.action].target != ActionTarget::User {
+        target_selection.action = card.action;
+        next_menu_state.set(MenuState::Target);
+        return;
+    }
+
+    let action = Action::Hero {
+        hero: card.hero,
+        action: card.action,
+        target: Target::Hero(card.hero),
+    };
+
+    next_state.set(InfoPanelState::Battle);
+    action_event_writer.send(ActionEvent(action));
+}
+
+pub struct CardPlugin;
+
+impl Plugin for CardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HandSelection>()
+            .add_systems(Startup, load_cards)
+            .add_systems(OnEnter(BattleState::Player), draw_hand)
+            .add_systems(OnEnter(InfoPanelState::Menu), setup_hand)
+            .add_systems(
+                Update,
+                (
+                    play_cards.run_if(in_state(AppState::Run)),
+                    (hand_cards, hand_cursor).run_if(in_state(InfoPanelState::Menu)),
+                    (hand_cursor_change, hand_select)
+                        .chain()
+                        .in_set(GameInputSet)
+                        .run_if(in_state(MenuState::Action)),
+                ),
+            );
+    }
+}
@@ -1,54 +1,579 @@
-use bevy::prelude::*;
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    sprite::Anchor,
+    text::{update_text2d_layout, Text2dBounds, TextLayoutInfo},
+};
+use bevy_prng::WyRand;
+use bevy_rand::prelude::{ForkableRng, GlobalEntropy};
+use serde::Deserialize;
+use thiserror::Error;
 
-#[derive(Component)]
-pub struct Enemy {
-    pub current_hp: isize,
+use crate::{
+    battle::{EnemyAttack, EnemyState, IntentKind, Target},
+    health_bar::{spawn_health_bar, BAR_HEIGHT},
+    hero::Player,
+    mouse::MousePosition,
+    status::StatusEffect,
+    tween::{Tween, TweenSystem, FADED_COLOR},
+    AppState, MenuState, ENEMY_COL_WIDTH, ENEMY_SPRITE_SIZE, HOVER_COLOR, UNIT_SIZE,
+};
+
+const INTENT_ICON_SIZE: f32 = 8.0;
+const INTENT_GAP: f32 = 4.0;
+
+#[derive(Asset, Debug, TypePath)]
+pub struct EnemyDefinition {
+    pub attacks: Vec<EnemyAttack>,
     pub max_hp: isize,
-    name: String,
+    pub name: String,
+    #[dependency]
+    pub sprite: Handle<Image>,
 }
 
-impl Enemy {
-    pub fn new(name: &str, hp: isize) -> Self {
-        Self {
-            current_hp: hp,
-            max_hp: hp,
-            name: name.to_string(),
+// Also read by the simulator, which has no asset server
+#[derive(Deserialize)]
+pub struct EnemyDefinitionFile {
+    pub attacks: Vec<EnemyAttack>,
+    pub max_hp: isize,
+    pub name: String,
+    pub sprite: String,
+}
+
+impl EnemyDefinitionFile {
+    pub fn validate(&self) -> Result<(), EnemyLoaderError> {
+        if self.attacks.is_empty() {
+            return Err(EnemyLoaderError::NoAttacks(self.name.clone()));
         }
+
+        if self.attacks.iter().all(|attack| attack.weight == 0) {
+            return Err(EnemyLoaderError::NoWeightedAttacks(self.name.clone()));
+        }
+
+        Ok(())
     }
 }
 
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum EnemyLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("Enemy {0} has no attacks")]
+    NoAttacks(String),
+    #[error("Enemy {0} has no attack with a weight above 0")]
+    NoWeightedAttacks(String),
+}
+
+#[derive(Default)]
+struct EnemyDefinitionLoader;
+
+impl AssetLoader for EnemyDefinitionLoader {
+    type Asset = EnemyDefinition;
+    type Settings = ();
+    type Error = EnemyLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let file = ron::de::from_bytes::<EnemyDefinitionFile>(&bytes)?;
+
+        file.validate()?;
+
+        Ok(EnemyDefinition {
+            attacks: file.attacks,
+            max_hp: file.max_hp,
+            name: file.name,
+            sprite: load_context.load(file.sprite),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["enemy.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct Encounter(pub Vec<Handle<EnemyDefinition>>);
+
+#[derive(Component, Deref, DerefMut)]
+pub struct Enemy {
+    pub index: usize,
+    #[deref]
+    pub state: EnemyState,
+}
+
 #[derive(Component)]
-pub struct EnemyHealthText;
+pub struct EnemyDisplay;
 
 #[derive(Component)]
-pub struct EnemyNameText;
+pub struct EnemyHealthText(pub usize);
+
+#[derive(Component)]
+pub struct EnemyNode;
+
+#[derive(Component)]
+pub struct EnemyNameText(pub usize);
+
+#[derive(Component)]
+pub struct EnemyStatusText(pub usize);
+
+#[derive(Component)]
+pub struct EnemyIntentIcon(pub usize);
+
+#[derive(Component)]
+pub struct EnemyIntentText(pub usize);
+
+#[derive(Resource)]
+pub struct IntentIcons {
+    attack: Handle<Image>,
+    buff: Handle<Image>,
+    debuff: Handle<Image>,
+    defend: Handle<Image>,
+}
+
+impl IntentIcons {
+    fn for_kind(&self, kind: IntentKind) -> &Handle<Image> {
+        match kind {
+            IntentKind::Attack => &self.attack,
+            IntentKind::Buff => &self.buff,
+            IntentKind::Debuff => &self.debuff,
+            IntentKind::Defend => &self.defend,
+        }
+    }
+}
+
+fn load_intent_icons(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(IntentIcons {
+        attack: asset_server.load("intent_attack.png"),
+        buff: asset_server.load("intent_buff.png"),
+        debuff: asset_server.load("intent_debuff.png"),
+        defend: asset_server.load("intent_defend.png"),
+    });
+}
+
+pub fn enemy_offset(index: usize, count: usize) -> f32 {
+    (index as f32 - (count as f32 - 1.0) * 0.5) * ENEMY_COL_WIDTH
+}
+
+pub fn spawn_enemy(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    display_query: Query<Entity, With<EnemyDisplay>>,
+    encounter: Res<Encounter>,
+    enemy_definitions: Res<Assets<EnemyDefinition>>,
+    mut global_entropy: ResMut<GlobalEntropy<WyRand>>,
+    intent_icons: Res<IntentIcons>,
+) {
+    let font_handle = asset_server.load("fonts/press_start_2p.ttf");
+
+    let Ok(display_entity_id) = display_query.get_single() else {
+        return;
+    };
+
+    let definitions = encounter
+        .0
+        .iter()
+        .filter_map(|handle| enemy_definitions.get(handle))
+        .collect::<Vec<_>>();
+
+    commands.entity(display_entity_id).with_children(|parent| {
+        for (i, definition) in definitions.iter().enumerate() {
+            let enemy_name_y = (ENEMY_SPRITE_SIZE + UNIT_SIZE) * 0.5;
+            let enemy_info_y = -enemy_name_y;
+            let enemy_status_y = enemy_info_y - UNIT_SIZE * 0.5;
+            let enemy_intent_y = enemy_name_y + UNIT_SIZE * 0.5;
+
+            // Tell apart enemies that share a name
+
+            let same_name = definitions
+                .iter()
+                .filter(|other| other.name == definition.name)
+                .count();
+            let name = if same_name > 1 {
+                let letter = definitions[..i]
+                    .iter()
+                    .filter(|other| other.name == definition.name)
+                    .count() as u8;
+
+                format!("{} {}", definition.name, (b'A' + letter) as char)
+            } else {
+                definition.name.clone()
+            };
+
+            parent
+                .spawn((
+                    EnemyNode,
+                    SpatialBundle {
+                        transform: Transform {
+                            translation: Vec3::new(enemy_offset(i, definitions.len()), 0.0, 0.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    // Enemy sprite display
+
+                    parent.spawn((
+                        Enemy {
+                            index: i,
+                            state: EnemyState::new(
+                                &name,
+                                definition.max_hp,
+                                definition.attacks.clone(),
+                            ),
+                        },
+                        global_entropy.fork_rng(),
+                        SpriteBundle {
+                            texture: definition.sprite.clone(),
+                            ..default()
+                        },
+                    ));
+
+                    // Enemy name
+
+                    parent.spawn((
+                        EnemyNameText(i),
+                        Text2dBundle {
+                            text: Text::from_section(
+                                "EnemyNameText",
+                                TextStyle {
+                                    font: font_handle.clone(),
+                                    font_size: 8.0,
+                                    ..default()
+                                },
+                            ),
+                            transform: Transform {
+                                translation: Vec3::new(0.0, enemy_name_y, 0.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ));
+
+                    // Enemy intent, laid out once its text is known
+
+                    parent.spawn((
+                        EnemyIntentIcon(i),
+                        SpriteBundle {
+                            texture: intent_icons.attack.clone(),
+                            transform: Transform {
+                                translation: Vec3::new(0.0, enemy_intent_y, 0.0),
+                                ..default()
+                            },
+                            visibility: Visibility::Hidden,
+                            ..default()
+                        },
+                    ));
+
+                    parent.spawn((
+                        EnemyIntentText(i),
+                        Text2dBundle {
+                            text: Text::from_section(
+                                "",
+                                TextStyle {
+                                    font: font_handle.clone(),
+                                    font_size: 8.0,
+                                    ..default()
+                                },
+                            ),
+                            // Wraps rather than spill into the next column
+                            text_2d_bounds: Text2dBounds {
+                                size: Vec2::new(
+                                    ENEMY_COL_WIDTH - INTENT_ICON_SIZE - INTENT_GAP,
+                                    f32::INFINITY,
+                                ),
+                            },
+                            text_anchor: Anchor::CenterLeft,
+                            transform: Transform {
+                                translation: Vec3::new(0.0, enemy_intent_y, 0.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ));
+
+                    // Enemy health
+
+                    spawn_health_bar(
+                        &asset_server,
+                        parent,
+                        Target::Enemy(i),
+                        Vec3::new(0.0, -(ENEMY_SPRITE_SIZE * 0.5 + BAR_HEIGHT * 0.75), 0.0),
+                        ENEMY_SPRITE_SIZE,
+                    );
+
+                    parent.spawn((
+                        EnemyHealthText(i),
+                        Text2dBundle {
+                            transform: Transform {
+                                translation: Vec3::new(0.0, enemy_info_y, 0.0),
+                                ..default()
+                            },
+                            text: Text::from_section(
+                                "EnemyHealthText",
+                                TextStyle {
+                                    font: font_handle.clone(),
+                                    font_size: 8.0,
+                                    ..default()
+                                },
+                            ),
+                            ..default()
+                        },
+                    ));
+
+                    // Enemy statuses
+
+                    parent.spawn((
+                        EnemyStatusText(i),
+                        Text2dBundle {
+                            transform: Transform {
+                                translation: Vec3::new(0.0, enemy_status_y, 0.0),
+                                ..default()
+                            },
+                            text: Text::from_section(
+                                "",
+                                TextStyle {
+                                    font: font_handle.clone(),
+                                    font_size: 8.0,
+                                    ..default()
+                                },
+                            ),
+                            ..default()
+                        },
+                    ));
+                });
+        }
+    });
+}
+
+pub fn despawn_enemies(mut commands: Commands, node_query: Query<Entity, With<EnemyNode>>) {
+    for entity_id in node_query.iter() {
+        commands.entity(entity_id).despawn_recursive();
+    }
+}
 
 fn enemy_health_text(
-    mut enemy_health_query: Query<&mut Text, With<EnemyHealthText>>,
+    mut enemy_health_query: Query<(&EnemyHealthText, &mut Text)>,
     enemy_query: Query<&Enemy>,
 ) {
-    let enemy = enemy_query.single();
-    let mut text = enemy_health_query.single_mut();
+    for enemy in enemy_query.iter() {
+        for (marker, mut text) in enemy_health_query.iter_mut() {
+            if marker.0 != enemy.index {
+                continue;
+            }
 
-    text.sections[0].value = format!("HP: {}/{}", enemy.current_hp, enemy.max_hp);
+            text.sections[0].value = if enemy.is_dead() {
+                "DEAD".to_string()
+            } else {
+                format!("HP: {}/{}", enemy.current_hp, enemy.max_hp)
+            };
+        }
+    }
 }
 
 fn enemy_name_text(
-    mut enemy_name_query: Query<&mut Text, With<EnemyNameText>>,
+    mut enemy_name_query: Query<(&EnemyNameText, &mut Text)>,
+    enemy_query: Query<&Enemy>,
+) {
+    for enemy in enemy_query.iter() {
+        for (marker, mut text) in enemy_name_query.iter_mut() {
+            if marker.0 != enemy.index {
+                continue;
+            }
+
+            // Update the name
+
+            text.sections[0].value = enemy.name.to_string();
+        }
+    }
+}
+
+fn enemy_status_text(
+    mut enemy_status_query: Query<(&EnemyStatusText, &mut Text)>,
     enemy_query: Query<&Enemy>,
 ) {
-    let enemy = enemy_query.single();
-    let mut text = enemy_name_query.single_mut();
+    for enemy in enemy_query.iter() {
+        for (marker, mut text) in enemy_status_query.iter_mut() {
+            if marker.0 != enemy.index {
+                continue;
+            }
 
-    // Update the name
+            text.sections[0].value = enemy
+                .statuses
+                .iter()
+                .map(|status| status.kind.label())
+                .collect::<Vec<_>>()
+                .join(" ");
+        }
+    }
+}
 
-    text.sections[0].value = enemy.name.to_string();
+// What each enemy is about to do: the damage it will deal after its own
+// statuses and who it will hit, which follows the front hero for attacks
+// aimed there
+fn enemy_intent(
+    enemy_query: Query<Ref<Enemy>>,
+    mut icon_query: Query<(&EnemyIntentIcon, &mut Handle<Image>, &mut Visibility)>,
+    intent_icons: Res<IntentIcons>,
+    player: Res<Player>,
+    mut text_query: Query<(&EnemyIntentText, &mut Text)>,
+) {
+    for enemy in enemy_query.iter() {
+        if !enemy.is_changed() && !player.is_changed() {
+            continue;
+        }
+
+        let intent = enemy.intent.filter(|_| enemy.can_act());
+
+        let (value, kind) = match intent {
+            Some(intent) => {
+                let attack = &enemy.attacks[intent.attack];
+                let damage = enemy.statuses.damage_dealt(attack.damage).max(0);
+                let hero = &player.heroes[enemy.intended_target(&intent, &player)].name;
+                let labels = |statuses: &[StatusEffect]| {
+                    statuses
+                        .iter()
+                        .map(|status| status.kind.label())
+                        .collect::<Vec<_>>()
+                        .join("+")
+                };
+
+                let value = match attack.kind() {
+                    IntentKind::Attack if attack.inflicts.is_empty() => {
+                        format!("{damage} {hero}")
+                    }
+                    IntentKind::Attack => format!("{damage}+{} {hero}", labels(&attack.inflicts)),
+                    IntentKind::Buff => labels(&attack.buffs),
+                    IntentKind::Debuff => format!("{} {hero}", labels(&attack.inflicts)),
+                    IntentKind::Defend => attack
+                        .buffs
+                        .iter()
+                        .filter(|status| status.kind.definition().defends)
+                        .map(|status| status.amount)
+                        .sum::<isize>()
+                        .to_string(),
+                };
+
+                (value, attack.kind())
+            }
+            None => (String::new(), IntentKind::Attack),
+        };
+
+        for (marker, mut texture, mut visibility) in icon_query.iter_mut() {
+            if marker.0 != enemy.index {
+                continue;
+            }
+
+            let handle = intent_icons.for_kind(kind);
+
+            if *texture != *handle {
+                *texture = handle.clone();
+            }
+
+            let shown = if intent.is_some() {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+
+            if *visibility != shown {
+                *visibility = shown;
+            }
+        }
+
+        for (marker, mut text) in text_query.iter_mut() {
+            if marker.0 != enemy.index || text.sections[0].value == value {
+                continue;
+            }
+
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+// Centers the icon and text together once the text has been measured
+fn enemy_intent_layout(
+    mut icon_query: Query<(&EnemyIntentIcon, &mut Transform)>,
+    mut text_query: Query<
+        (&EnemyIntentText, &TextLayoutInfo, &mut Transform),
+        (Changed<TextLayoutInfo>, Without<EnemyIntentIcon>),
+    >,
+) {
+    for (text_marker, layout, mut text_transform) in text_query.iter_mut() {
+        let width = INTENT_ICON_SIZE + INTENT_GAP + layout.logical_size.x;
+        let left = -(width * 0.5).round();
+
+        text_transform.translation.x = left + INTENT_ICON_SIZE + INTENT_GAP;
+
+        for (icon_marker, mut icon_transform) in icon_query.iter_mut() {
+            if icon_marker.0 == text_marker.0 {
+                icon_transform.translation.x = left + INTENT_ICON_SIZE * 0.5;
+            }
+        }
+    }
+}
+
+fn enemy_hover(
+    mut enemy_query: Query<(&Enemy, &GlobalTransform, &mut Sprite), Without<Tween>>,
+    menu_state: Option<Res<State<MenuState>>>,
+    mouse_position: Res<MousePosition>,
+) {
+    for (enemy, transform, mut sprite) in enemy_query.iter_mut() {
+        let is_hovered = menu_state.is_some()
+            && mouse_position.is_over(
+                transform.translation().truncate(),
+                Vec2::splat(ENEMY_SPRITE_SIZE),
+            );
+
+        let color = if enemy.is_dead() {
+            FADED_COLOR
+        } else if is_hovered {
+            HOVER_COLOR
+        } else {
+            Color::WHITE
+        };
+
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
 }
 
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (enemy_health_text, enemy_name_text));
+        app.init_asset::<EnemyDefinition>()
+            .init_asset_loader::<EnemyDefinitionLoader>()
+            .add_systems(Startup, load_intent_icons)
+            .add_systems(
+                Update,
+                (
+                    enemy_health_text,
+                    enemy_intent,
+                    enemy_name_text,
+                    enemy_status_text,
+                )
+                    .run_if(in_state(AppState::Run)),
+            )
+            // Once this frame's tweens have started, so they aren't fought over
+            .add_systems(
+                PostUpdate,
+                (
+                    enemy_hover.after(TweenSystem),
+                    enemy_intent_layout.after(update_text2d_layout),
+                )
+                    .run_if(in_state(AppState::Run)),
+            );
     }
 }
@@ -0,0 +1,186 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{battle::Target, enemy::Enemy, hero::Player, AppState};
+
+pub const BAR_HEIGHT: f32 = 8.0;
+// Room for the frame's border around the fill
+const BAR_INSET: f32 = 2.0;
+
+const FILL_COLOR: Color = Color::srgb(0.85, 0.2, 0.2);
+const GHOST_COLOR: Color = Color::srgb(1.0, 0.85, 0.6);
+
+// Share of the bar the fill covers a second as it catches up
+const DRAIN_RATE: f32 = 1.0;
+// How long the ghost hangs around after a hit before it follows
+const GHOST_DELAY: f32 = 0.6;
+const GHOST_RATE: f32 = 0.5;
+
+#[derive(Component)]
+pub struct HealthBar {
+    pub target: Target,
+    fill: f32,
+    ghost: f32,
+    hold: f32,
+    width: f32,
+}
+
+#[derive(Component)]
+struct HealthFill;
+
+#[derive(Component)]
+struct HealthGhost;
+
+pub fn spawn_health_bar(
+    asset_server: &AssetServer,
+    parent: &mut ChildBuilder,
+    target: Target,
+    translation: Vec3,
+    width: f32,
+) {
+    let inner_width = width - BAR_INSET * 2.0;
+    let left = -(inner_width * 0.5);
+
+    parent
+        .spawn((
+            HealthBar {
+                target,
+                fill: 1.0,
+                ghost: 1.0,
+                hold: 0.0,
+                width: inner_width,
+            },
+            SpatialBundle {
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ImageScaleMode::Sliced(TextureSlicer {
+                    border: BorderRect::square(4.0),
+                    center_scale_mode: SliceScaleMode::Stretch,
+                    sides_scale_mode: SliceScaleMode::Stretch,
+                    max_corner_scale: 1.0,
+                }),
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(width, BAR_HEIGHT)),
+                        ..default()
+                    },
+                    texture: asset_server.load("basic_background.png"),
+                    ..default()
+                },
+            ));
+
+            // Recent damage, behind the fill
+
+            parent.spawn((
+                HealthGhost,
+                SpriteBundle {
+                    sprite: Sprite {
+                        anchor: Anchor::CenterLeft,
+                        color: GHOST_COLOR,
+                        custom_size: Some(Vec2::new(inner_width, BAR_HEIGHT - BAR_INSET * 2.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(left, 0.0, 0.1),
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                HealthFill,
+                SpriteBundle {
+                    sprite: Sprite {
+                        anchor: Anchor::CenterLeft,
+                        color: FILL_COLOR,
+                        custom_size: Some(Vec2::new(inner_width, BAR_HEIGHT - BAR_INSET * 2.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(left, 0.0, 0.2),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn health_fraction(target: Target, enemy_query: &Query<&Enemy>, player: &Player) -> f32 {
+    let (current_hp, max_hp) = match target {
+        Target::Enemy(index) => {
+            let Some(enemy) = enemy_query.iter().find(|enemy| enemy.index == index) else {
+                return 0.0;
+            };
+
+            (enemy.current_hp, enemy.max_hp)
+        }
+        Target::Hero(index) => {
+            let hero = &player.heroes[index];
+
+            (hero.current_hp, hero.max_hp)
+        }
+    };
+
+    (current_hp.max(0) as f32 / max_hp.max(1) as f32).min(1.0)
+}
+
+fn animate_health_bars(
+    mut bar_query: Query<(&mut HealthBar, &Children)>,
+    enemy_query: Query<&Enemy>,
+    mut fill_query: Query<&mut Sprite, (With<HealthFill>, Without<HealthGhost>)>,
+    mut ghost_query: Query<&mut Sprite, (With<HealthGhost>, Without<HealthFill>)>,
+    player: Res<Player>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+
+    for (mut bar, children) in bar_query.iter_mut() {
+        let fraction = health_fraction(bar.target, &enemy_query, &player);
+
+        // Restored runs start where they left off rather than draining down
+        if bar.is_added() {
+            bar.fill = fraction;
+            bar.ghost = fraction;
+        }
+
+        if fraction < bar.fill {
+            bar.fill = (bar.fill - DRAIN_RATE * delta).max(fraction);
+            bar.hold = GHOST_DELAY;
+        } else {
+            bar.fill = (bar.fill + DRAIN_RATE * delta).min(fraction);
+        }
+
+        // Healing pulls the ghost up with it, damage leaves it behind for a
+        // moment
+        if bar.ghost <= bar.fill {
+            bar.ghost = bar.fill;
+        } else if bar.hold > 0.0 {
+            bar.hold -= delta;
+        } else {
+            bar.ghost = (bar.ghost - GHOST_RATE * delta).max(bar.fill);
+        }
+
+        for child in children.iter() {
+            if let Ok(mut sprite) = fill_query.get_mut(*child) {
+                sprite.custom_size = Some(Vec2::new(
+                    (bar.width * bar.fill).round(),
+                    BAR_HEIGHT - BAR_INSET * 2.0,
+                ));
+            }
+
+            if let Ok(mut sprite) = ghost_query.get_mut(*child) {
+                sprite.custom_size = Some(Vec2::new(
+                    (bar.width * bar.ghost).round(),
+                    BAR_HEIGHT - BAR_INSET * 2.0,
+                ));
+            }
+        }
+    }
+}
+
+pub struct HealthBarPlugin;
+
+impl Plugin for HealthBarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, animate_health_bars.run_if(in_state(AppState::Run)));
+    }
+}
@@ -0,0 +1,295 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    battle::{ActionDefinition, HeroState, PartyState},
+    input::{GameInputSet, InputAction, InputActions},
+    mouse::MousePosition,
+    tween::{Tween, TweenSystem, FADED_COLOR},
+    AppState, MenuState, HERO_COL_WIDTH, HERO_SPRITE_SIZE, HOVER_COLOR,
+};
+
+#[derive(Asset, Debug, Deserialize, TypePath)]
+pub struct HeroDefinition {
+    pub actions: Vec<ActionDefinition>,
+    pub max_hp: isize,
+    pub name: String,
+    pub sprite_index: usize,
+}
+
+#[derive(Asset, Debug, TypePath)]
+pub struct PartyDefinition {
+    #[dependency]
+    pub heroes: Vec<Handle<HeroDefinition>>,
+}
+
+// Also read by the simulator, which has no asset server
+#[derive(Deserialize)]
+pub struct PartyDefinitionFile {
+    pub heroes: Vec<String>,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum HeroLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+#[derive(Default)]
+struct HeroDefinitionLoader;
+
+impl AssetLoader for HeroDefinitionLoader {
+    type Asset = HeroDefinition;
+    type Settings = ();
+    type Error = HeroLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        Ok(ron::de::from_bytes::<HeroDefinition>(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["hero.ron"]
+    }
+}
+
+#[derive(Default)]
+struct PartyDefinitionLoader;
+
+impl AssetLoader for PartyDefinitionLoader {
+    type Asset = PartyDefinition;
+    type Settings = ();
+    type Error = HeroLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let file = ron::de::from_bytes::<PartyDefinitionFile>(&bytes)?;
+
+        Ok(PartyDefinition {
+            heroes: file
+                .heroes
+                .into_iter()
+                .map(|path| load_context.load(path))
+                .collect(),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["party.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct PartyHandle(pub Handle<PartyDefinition>);
+
+#[derive(Deref, DerefMut, Resource)]
+pub struct Player(pub PartyState);
+
+#[derive(SystemParam)]
+pub struct HeroDefinitions<'w> {
+    hero_definitions: Res<'w, Assets<HeroDefinition>>,
+    party_definitions: Res<'w, Assets<PartyDefinition>>,
+    party_handle: Res<'w, PartyHandle>,
+}
+
+impl HeroDefinitions<'_> {
+    pub fn iter(&self) -> impl Iterator<Item = &HeroDefinition> {
+        self.party_definitions
+            .get(&self.party_handle.0)
+            .into_iter()
+            .flat_map(|party| party.heroes.iter())
+            .filter_map(|handle| self.hero_definitions.get(handle))
+    }
+}
+
+#[derive(Component)]
+pub struct Hero {
+    pub index: usize,
+    pub sprite_index: usize,
+}
+
+#[derive(Component)]
+pub struct HeroHealthText(pub usize);
+
+#[derive(Component)]
+pub struct HeroStatusText(pub usize);
+
+#[derive(Component)]
+pub struct HeroArrow;
+
+// The player moved on to the next hero who can act
+#[derive(Event)]
+pub struct HeroCycled;
+
+pub fn hero_offset(index: usize, count: usize) -> f32 {
+    (index as f32 - (count as f32 - 1.0) * 0.5) * HERO_COL_WIDTH
+}
+
+fn load_party(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(PartyHandle(asset_server.load("party.ron")));
+}
+
+pub fn insert_player(mut commands: Commands, hero_definitions: HeroDefinitions) {
+    let heroes = hero_definitions
+        .iter()
+        .map(|definition| {
+            HeroState::new(
+                &definition.name,
+                definition.max_hp,
+                definition.actions.clone(),
+            )
+        })
+        .collect();
+
+    commands.insert_resource(Player(PartyState::new(heroes)));
+}
+
+fn hero_arrow(mut hero_arrow_query: Query<&mut Transform, With<HeroArrow>>, player: Res<Player>) {
+    let mut transform = hero_arrow_query.single_mut();
+
+    transform.translation.x = hero_offset(player.current_hero, player.heroes.len());
+}
+
+fn hero_health_status(
+    hero_query: Query<&Hero>,
+    player: Res<Player>,
+    mut text_query: Query<(&mut HeroHealthText, &mut Text)>,
+) {
+    for hero in hero_query.iter() {
+        for (marker, mut hero_health_text) in text_query.iter_mut() {
+            if hero.index != marker.0 {
+                continue;
+            }
+
+            let state = &player.heroes[hero.index];
+
+            hero_health_text.sections[0].value =
+                format!("HP: {}/{}", state.current_hp, state.max_hp);
+        }
+    }
+}
+
+fn hero_animation(mut hero_query: Query<(&mut Hero, &mut TextureAtlas)>, player: Res<Player>) {
+    for (hero, mut texture_atlas) in hero_query.iter_mut() {
+        let state = &player.heroes[hero.index];
+
+        texture_atlas.index = hero.sprite_index + (if !state.can_act() { 1 } else { 0 });
+    }
+}
+
+fn hero_status(player: Res<Player>, mut text_query: Query<(&mut HeroStatusText, &mut Text)>) {
+    for (hero_status_text, mut text) in text_query.iter_mut() {
+        let state = &player.heroes[hero_status_text.0];
+
+        if state.is_dead() {
+            text.sections[0].value = "DEAD".to_string();
+            text.sections[0].style.color = Color::default();
+        } else {
+            text.sections[0].value = state
+                .statuses
+                .iter()
+                .map(|status| status.kind.label())
+                .collect::<Vec<_>>()
+                .join("\n");
+            text.sections[0].style.color = Color::default();
+        }
+    }
+}
+
+fn hero_hover(
+    mut hero_query: Query<(&Hero, &GlobalTransform, &mut Sprite), Without<Tween>>,
+    menu_state: Option<Res<State<MenuState>>>,
+    mouse_position: Res<MousePosition>,
+    player: Res<Player>,
+) {
+    for (hero, transform, mut sprite) in hero_query.iter_mut() {
+        let is_hovered = menu_state.is_some()
+            && mouse_position.is_over(
+                transform.translation().truncate(),
+                Vec2::splat(HERO_SPRITE_SIZE),
+            );
+
+        let color = if player.heroes[hero.index].is_dead() {
+            FADED_COLOR
+        } else if is_hovered {
+            HOVER_COLOR
+        } else {
+            Color::WHITE
+        };
+
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+fn hero_select(
+    hero_query: Query<(&Hero, &GlobalTransform)>,
+    input_actions: Res<InputActions>,
+    mouse_position: Res<MousePosition>,
+    mut player: ResMut<Player>,
+) {
+    if !input_actions.just_clicked(InputAction::Confirm) {
+        return;
+    }
+
+    for (hero, transform) in hero_query.iter() {
+        if mouse_position.is_over(
+            transform.translation().truncate(),
+            Vec2::splat(HERO_SPRITE_SIZE),
+        ) {
+            player.select_hero(hero.index);
+        }
+    }
+}
+
+pub struct HeroPlugin;
+
+impl Plugin for HeroPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HeroCycled>()
+            .init_asset::<HeroDefinition>()
+            .init_asset::<PartyDefinition>()
+            .init_asset_loader::<HeroDefinitionLoader>()
+            .init_asset_loader::<PartyDefinitionLoader>()
+            .add_systems(Startup, load_party)
+            .add_systems(
+                Update,
+                (
+                    (hero_arrow, hero_animation, hero_health_status, hero_status)
+                        .run_if(in_state(AppState::Run)),
+                    hero_select
+                        .in_set(GameInputSet)
+                        .run_if(in_state(MenuState::Action)),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                hero_hover
+                    .after(TweenSystem)
+                    .run_if(in_state(AppState::Run)),
+            );
+    }
+}
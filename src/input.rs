@@ -0,0 +1,692 @@
+use std::collections::{BTreeMap, HashSet};
+
+use bevy::{input::InputSystem, prelude::*, sprite::Anchor};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::{AudioSettings, VOLUME_STEP},
+    is_persistent,
+    save::{read_stored, write_stored},
+    AppState, Persistent, GAME_HEIGHT, GAME_WIDTH, MENU_BLOCK, UNIT_SIZE,
+};
+
+const BINDINGS_PATH: &str = "input.ron";
+
+// Keys, buttons or mouse buttons an action can have of each kind
+const BINDING_SLOTS: usize = 2;
+
+const STICK_THRESHOLD: f32 = 0.5;
+
+// Every action, both volumes and reset
+const OPTIONS_ROWS: usize = InputAction::ALL.len() + 3;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum InputAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Cancel,
+    Cycle,
+    Log,
+    Options,
+    Fullscreen,
+    Mute,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 11] = [
+        InputAction::Up,
+        InputAction::Down,
+        InputAction::Left,
+        InputAction::Right,
+        InputAction::Confirm,
+        InputAction::Cancel,
+        InputAction::Cycle,
+        InputAction::Log,
+        InputAction::Options,
+        InputAction::Fullscreen,
+        InputAction::Mute,
+    ];
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Binding {
+    #[serde(default)]
+    pub buttons: Vec<GamepadButtonType>,
+    #[serde(default)]
+    pub keys: Vec<KeyCode>,
+    #[serde(default)]
+    pub mouse: Vec<MouseButton>,
+}
+
+impl Binding {
+    fn new(keys: &[KeyCode], buttons: &[GamepadButtonType], mouse: &[MouseButton]) -> Self {
+        Self {
+            buttons: buttons.to_vec(),
+            keys: keys.to_vec(),
+            mouse: mouse.to_vec(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        let names = self
+            .keys
+            .iter()
+            .map(|key| format!("{key:?}"))
+            .chain(self.buttons.iter().map(|button| format!("Pad {button:?}")))
+            .chain(self.mouse.iter().map(|button| format!("Mouse {button:?}")))
+            .collect::<Vec<_>>();
+
+        if names.is_empty() {
+            "-".to_string()
+        } else {
+            names.join(", ")
+        }
+    }
+}
+
+// Replaces one slot of a binding, leaving the rest of that kind alone
+fn set_slot<T: PartialEq>(inputs: &mut Vec<T>, slot: usize, input: T) {
+    if inputs.contains(&input) {
+        return;
+    }
+
+    match inputs.get_mut(slot) {
+        Some(current) => *current = input,
+        None => inputs.push(input),
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Resource, Serialize)]
+pub struct InputBindings(pub BTreeMap<InputAction, Binding>);
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use GamepadButtonType as Pad;
+
+        Self(BTreeMap::from([
+            (
+                InputAction::Up,
+                Binding::new(&[KeyCode::ArrowUp, KeyCode::KeyW], &[Pad::DPadUp], &[]),
+            ),
+            (
+                InputAction::Down,
+                Binding::new(&[KeyCode::ArrowDown, KeyCode::KeyS], &[Pad::DPadDown], &[]),
+            ),
+            (
+                InputAction::Left,
+                Binding::new(&[KeyCode::ArrowLeft, KeyCode::KeyA], &[Pad::DPadLeft], &[]),
+            ),
+            (
+                InputAction::Right,
+                Binding::new(
+                    &[KeyCode::ArrowRight, KeyCode::KeyD],
+                    &[Pad::DPadRight],
+                    &[],
+                ),
+            ),
+            (
+                InputAction::Confirm,
+                Binding::new(
+                    &[KeyCode::Enter, KeyCode::Space],
+                    &[Pad::South],
+                    &[MouseButton::Left],
+                ),
+            ),
+            (
+                InputAction::Cancel,
+                Binding::new(
+                    &[KeyCode::Escape, KeyCode::Backspace],
+                    &[Pad::East],
+                    &[MouseButton::Right],
+                ),
+            ),
+            (
+                InputAction::Cycle,
+                Binding::new(&[KeyCode::Tab], &[Pad::North], &[]),
+            ),
+            (
+                InputAction::Log,
+                Binding::new(&[KeyCode::KeyL], &[Pad::Select], &[]),
+            ),
+            (
+                InputAction::Options,
+                Binding::new(&[KeyCode::F1], &[Pad::Start], &[]),
+            ),
+            (
+                InputAction::Fullscreen,
+                Binding::new(&[KeyCode::F11], &[], &[]),
+            ),
+            (InputAction::Mute, Binding::new(&[KeyCode::KeyM], &[], &[])),
+        ]))
+    }
+}
+
+impl InputBindings {
+    fn load() -> Self {
+        let Some(contents) = read_stored(BINDINGS_PATH) else {
+            return Self::default();
+        };
+
+        match ron::from_str::<InputBindings>(&contents) {
+            Ok(mut bindings) => {
+                // Actions added since the file was written keep their defaults
+                for (action, binding) in Self::default().0 {
+                    bindings.0.entry(action).or_insert(binding);
+                }
+
+                bindings
+            }
+            Err(error) => {
+                warn!("Could not read {}: {}", BINDINGS_PATH, error);
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| write_stored(BINDINGS_PATH, &contents));
+
+        if let Err(error) = result {
+            warn!("Could not write {}: {}", BINDINGS_PATH, error);
+        }
+    }
+
+    pub fn get(&self, action: InputAction) -> Option<&Binding> {
+        self.0.get(&action)
+    }
+}
+
+// Logical actions triggered this frame. Mouse clicks are kept apart, since
+// they usually only count when something is under the pointer.
+#[derive(Debug, Default, Resource)]
+pub struct InputActions {
+    clicked: HashSet<InputAction>,
+    pressed: HashSet<InputAction>,
+    stick: IVec2,
+}
+
+impl InputActions {
+    pub fn just_clicked(&self, action: InputAction) -> bool {
+        self.clicked.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_triggered(&self, action: InputAction) -> bool {
+        self.just_pressed(action) || self.just_clicked(action)
+    }
+}
+
+// Systems that drive the game from input, paused while the options or the log
+// are open
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SystemSet)]
+pub struct GameInputSet;
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, States)]
+pub enum OptionsState {
+    #[default]
+    Closed,
+    Open,
+}
+
+#[derive(Default, Resource)]
+struct OptionsMenu {
+    is_rebinding: bool,
+    selection: usize,
+    slot: usize,
+}
+
+#[derive(Component)]
+struct OptionsNode;
+
+#[derive(Component)]
+struct OptionsArrow;
+
+#[derive(Component)]
+struct OptionsRow(usize);
+
+#[derive(Clone, Copy, Debug)]
+enum OptionsItem {
+    Action(InputAction),
+    MusicVolume,
+    SoundVolume,
+    Reset,
+}
+
+#[derive(Component)]
+struct OptionsHint;
+
+fn read_input(
+    axes: Res<Axis<GamepadAxis>>,
+    bindings: Res<InputBindings>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    mut input_actions: ResMut<InputActions>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+) {
+    input_actions.clicked.clear();
+    input_actions.pressed.clear();
+
+    for (action, binding) in bindings.0.iter() {
+        let is_pressed = binding.keys.iter().any(|key| keys.just_pressed(*key))
+            || gamepads.iter().any(|gamepad| {
+                binding.buttons.iter().any(|button_type| {
+                    buttons.just_pressed(GamepadButton::new(gamepad, *button_type))
+                })
+            });
+
+        if is_pressed {
+            input_actions.pressed.insert(*action);
+        }
+
+        if binding
+            .mouse
+            .iter()
+            .any(|button| mouse_buttons.just_pressed(*button))
+        {
+            input_actions.clicked.insert(*action);
+        }
+    }
+
+    // The left stick always navigates, counting as a press when pushed past
+    // the threshold
+
+    let mut stick = IVec2::ZERO;
+
+    for gamepad in gamepads.iter() {
+        let axis = |axis_type| {
+            let value = axes
+                .get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0);
+
+            if value > STICK_THRESHOLD {
+                1
+            } else if value < -STICK_THRESHOLD {
+                -1
+            } else {
+                0
+            }
+        };
+
+        let direction = IVec2::new(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        );
+
+        if direction != IVec2::ZERO {
+            stick = direction;
+        }
+    }
+
+    let previous = input_actions.stick;
+
+    for (is_pushed, action) in [
+        (stick.y < 0 && previous.y >= 0, InputAction::Down),
+        (stick.x < 0 && previous.x >= 0, InputAction::Left),
+        (stick.x > 0 && previous.x <= 0, InputAction::Right),
+        (stick.y > 0 && previous.y <= 0, InputAction::Up),
+    ] {
+        if is_pushed {
+            input_actions.pressed.insert(action);
+        }
+    }
+
+    input_actions.stick = stick;
+}
+
+fn load_bindings(mut bindings: ResMut<InputBindings>) {
+    *bindings = InputBindings::load();
+}
+
+fn toggle_options(
+    input_actions: Res<InputActions>,
+    options_menu: Res<OptionsMenu>,
+    mut next_state: ResMut<NextState<OptionsState>>,
+    state: Res<State<OptionsState>>,
+) {
+    if options_menu.is_rebinding || !input_actions.just_pressed(InputAction::Options) {
+        return;
+    }
+
+    next_state.set(match state.get() {
+        OptionsState::Closed => OptionsState::Open,
+        OptionsState::Open => OptionsState::Closed,
+    });
+}
+
+fn setup_options(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    mut options_menu: ResMut<OptionsMenu>,
+) {
+    let font_handle = asset_server.load("fonts/press_start_2p.ttf");
+
+    *options_menu = OptionsMenu::default();
+
+    commands
+        .spawn((
+            OptionsNode,
+            SpatialBundle {
+                transform: Transform {
+                    translation: Vec3::new(0.0, 0.0, 10.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            // Background
+
+            parent.spawn((
+                ImageScaleMode::Sliced(TextureSlicer {
+                    border: BorderRect::square(4.0),
+                    center_scale_mode: SliceScaleMode::Stretch,
+                    sides_scale_mode: SliceScaleMode::Stretch,
+                    max_corner_scale: 2.0,
+                }),
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(GAME_WIDTH * 0.75, GAME_HEIGHT * 0.75)),
+                        ..default()
+                    },
+                    texture: asset_server.load("basic_background.png"),
+                    transform: Transform {
+                        translation: Vec3::new(0.0, 0.0, -1.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+
+            // One row per action, then the volumes and reset
+
+            let left = -(GAME_WIDTH * 0.375) + UNIT_SIZE;
+            let top = GAME_HEIGHT * 0.375 - UNIT_SIZE * 0.5;
+            let row_height = MENU_BLOCK * 0.5;
+
+            for i in 0..OPTIONS_ROWS {
+                parent.spawn((
+                    OptionsRow(i),
+                    Text2dBundle {
+                        text: Text::from_section(
+                            "OptionsRow",
+                            TextStyle {
+                                font: font_handle.clone(),
+                                font_size: 8.0,
+                                ..default()
+                            },
+                        ),
+                        text_anchor: Anchor::CenterLeft,
+                        transform: Transform {
+                            translation: Vec3::new(left, top - (i as f32 * row_height), 0.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ));
+            }
+
+            parent.spawn((
+                OptionsHint,
+                Text2dBundle {
+                    text: Text::from_section(
+                        "OptionsHint",
+                        TextStyle {
+                            font: font_handle.clone(),
+                            font_size: 8.0,
+                            ..default()
+                        },
+                    ),
+                    text_anchor: Anchor::CenterLeft,
+                    transform: Transform {
+                        translation: Vec3::new(
+                            left,
+                            top - ((OPTIONS_ROWS + 1) as f32 * row_height),
+                            0.0,
+                        ),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+
+            // Cursor
+
+            parent.spawn((
+                OptionsArrow,
+                SpriteBundle {
+                    texture: asset_server.load("arrow_right.png"),
+                    transform: Transform {
+                        translation: Vec3::new(left - UNIT_SIZE * 0.5, top, 0.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn close_options(mut next_state: ResMut<NextState<OptionsState>>) {
+    next_state.set(OptionsState::Closed);
+}
+
+fn cleanup_options(mut commands: Commands, node_query: Query<Entity, With<OptionsNode>>) {
+    for entity_id in node_query.iter() {
+        commands.entity(entity_id).despawn_recursive();
+    }
+}
+
+fn options_item(row: usize) -> OptionsItem {
+    let actions = InputAction::ALL.len();
+
+    match row {
+        row if row < actions => OptionsItem::Action(InputAction::ALL[row]),
+        row if row == actions => OptionsItem::MusicVolume,
+        row if row == actions + 1 => OptionsItem::SoundVolume,
+        _ => OptionsItem::Reset,
+    }
+}
+
+fn options_text(
+    audio_settings: Res<AudioSettings>,
+    bindings: Res<InputBindings>,
+    mut hint_query: Query<&mut Text, (With<OptionsHint>, Without<OptionsRow>)>,
+    options_menu: Res<OptionsMenu>,
+    mut row_query: Query<(&OptionsRow, &mut Text)>,
+) {
+    for (row, mut text) in row_query.iter_mut() {
+        text.sections[0].value = match options_item(row.0) {
+            OptionsItem::Action(action) => {
+                let binding = bindings.get(action).cloned().unwrap_or_default();
+
+                format!("{:?}: {}", action, binding.describe())
+            }
+            OptionsItem::MusicVolume => {
+                format!("Music volume: {:.0}%", audio_settings.music_volume * 100.0)
+            }
+            OptionsItem::SoundVolume => {
+                format!("Sound volume: {:.0}%", audio_settings.sound_volume * 100.0)
+            }
+            OptionsItem::Reset => "Reset to defaults".to_string(),
+        };
+    }
+
+    for mut text in hint_query.iter_mut() {
+        text.sections[0].value = match options_item(options_menu.selection) {
+            OptionsItem::Action(action) if options_menu.is_rebinding => {
+                format!(
+                    "Press a key or button for {:?} slot {}, Cancel to stop",
+                    action,
+                    options_menu.slot + 1
+                )
+            }
+            OptionsItem::Action(_) => format!(
+                "Slot {}, Left/Right to change, Confirm to rebind",
+                options_menu.slot + 1
+            ),
+            OptionsItem::MusicVolume | OptionsItem::SoundVolume => {
+                "Left/Right to change, Cancel to close".to_string()
+            }
+            OptionsItem::Reset => "Confirm to reset, Cancel to close".to_string(),
+        };
+    }
+}
+
+fn options_cursor(
+    mut arrow_query: Query<&mut Transform, With<OptionsArrow>>,
+    options_menu: Res<OptionsMenu>,
+    row_query: Query<(&OptionsRow, &Transform), Without<OptionsArrow>>,
+) {
+    for (row, row_transform) in row_query.iter() {
+        if row.0 != options_menu.selection {
+            continue;
+        }
+
+        for mut transform in arrow_query.iter_mut() {
+            transform.translation.y = row_transform.translation.y;
+        }
+    }
+}
+
+fn options_input(
+    mut audio_settings: ResMut<AudioSettings>,
+    mut bindings: ResMut<InputBindings>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    input_actions: Res<InputActions>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut next_state: ResMut<NextState<OptionsState>>,
+    mut options_menu: ResMut<OptionsMenu>,
+    persistent: Res<Persistent>,
+) {
+    if options_menu.is_rebinding {
+        let OptionsItem::Action(action) = options_item(options_menu.selection) else {
+            options_menu.is_rebinding = false;
+            return;
+        };
+
+        if input_actions.just_triggered(InputAction::Cancel) {
+            options_menu.is_rebinding = false;
+            return;
+        }
+
+        let slot = options_menu.slot;
+        let binding = bindings.0.entry(action).or_default();
+
+        // Whatever gets pressed first takes the chosen slot of its kind
+        if let Some(key) = keys.get_just_pressed().next() {
+            set_slot(&mut binding.keys, slot, *key);
+        } else if let Some(button) = buttons.get_just_pressed().next() {
+            set_slot(&mut binding.buttons, slot, button.button_type);
+        } else if let Some(button) = mouse_buttons.get_just_pressed().next() {
+            set_slot(&mut binding.mouse, slot, *button);
+        } else {
+            return;
+        }
+
+        options_menu.is_rebinding = false;
+
+        if persistent.0 {
+            bindings.save();
+        }
+        return;
+    }
+
+    if input_actions.just_pressed(InputAction::Down) {
+        options_menu.selection = (options_menu.selection + 1) % OPTIONS_ROWS;
+    }
+
+    if input_actions.just_pressed(InputAction::Up) {
+        options_menu.selection = (options_menu.selection + OPTIONS_ROWS - 1) % OPTIONS_ROWS;
+    }
+
+    let step = match (
+        input_actions.just_pressed(InputAction::Left),
+        input_actions.just_pressed(InputAction::Right),
+    ) {
+        (true, false) => -VOLUME_STEP,
+        (false, true) => VOLUME_STEP,
+        _ => 0.0,
+    };
+
+    let volume = match options_item(options_menu.selection) {
+        OptionsItem::Action(_) => {
+            if step != 0.0 {
+                options_menu.slot = (options_menu.slot + 1) % BINDING_SLOTS;
+            }
+
+            None
+        }
+        OptionsItem::MusicVolume => Some(&mut audio_settings.music_volume),
+        OptionsItem::SoundVolume => Some(&mut audio_settings.sound_volume),
+        OptionsItem::Reset => None,
+    };
+
+    if let Some(volume) = volume {
+        if step != 0.0 {
+            // Rounded so repeated steps land back on whole tenths
+            *volume = ((*volume + step).clamp(0.0, 1.0) * 10.0).round() / 10.0;
+
+            if persistent.0 {
+                audio_settings.save();
+            }
+        }
+    }
+
+    if input_actions.just_triggered(InputAction::Cancel) {
+        next_state.set(OptionsState::Closed);
+        return;
+    }
+
+    if input_actions.just_pressed(InputAction::Confirm) {
+        match options_item(options_menu.selection) {
+            OptionsItem::Action(_) => options_menu.is_rebinding = true,
+            OptionsItem::MusicVolume | OptionsItem::SoundVolume => {}
+            OptionsItem::Reset => {
+                *audio_settings = AudioSettings::default();
+                *bindings = InputBindings::default();
+
+                if persistent.0 {
+                    audio_settings.save();
+                    bindings.save();
+                }
+            }
+        }
+    }
+}
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputBindings>()
+            .init_resource::<InputActions>()
+            .init_resource::<OptionsMenu>()
+            .init_state::<OptionsState>()
+            .configure_sets(Update, GameInputSet.run_if(in_state(OptionsState::Closed)))
+            .add_systems(Startup, load_bindings.run_if(is_persistent))
+            .add_systems(PreUpdate, read_input.after(InputSystem))
+            .add_systems(OnEnter(OptionsState::Open), setup_options)
+            .add_systems(OnExit(OptionsState::Open), cleanup_options)
+            .add_systems(OnExit(AppState::Run), close_options)
+            .add_systems(
+                Update,
+                (
+                    toggle_options
+                        .before(options_input)
+                        .run_if(in_state(AppState::Run)),
+                    (options_input, options_text, options_cursor)
+                        .chain()
+                        .run_if(in_state(OptionsState::Open)),
+                ),
+            );
+    }
+}
@@ -0,0 +1,781 @@
+pub mod ai;
+pub mod audio;
+pub mod battle;
+pub mod battle_log;
+pub mod canvas;
+pub mod card;
+pub mod enemy;
+pub mod health_bar;
+pub mod hero;
+pub mod input;
+pub mod mouse;
+pub mod popup;
+pub mod replay;
+pub mod save;
+pub mod status;
+pub mod target;
+pub mod title;
+pub mod tower;
+pub mod tween;
+
+use std::time::Duration;
+
+use ai::{seed_run, EnemyAct, EnemyAiPlugin, EnemyTurn, RunSeed};
+use audio::GameAudioPlugin;
+use battle::{Action, BattleOutcome, Target};
+use battle_log::{BattleLog, BattleLogPlugin};
+use bevy::{asset::AssetLoadFailedEvent, prelude::*, sprite::Anchor, text::Text2dBounds};
+use bevy_prng::WyRand;
+use bevy_rand::plugin::EntropyPlugin;
+use canvas::CanvasPlugin;
+use card::{insert_deck, CardPlugin, HandSelection};
+use enemy::{spawn_enemy, Enemy, EnemyDefinition, EnemyDisplay, EnemyPlugin};
+use health_bar::{spawn_health_bar, HealthBarPlugin, BAR_HEIGHT};
+use hero::{
+    hero_offset, insert_player, Hero, HeroArrow, HeroDefinition, HeroDefinitions, HeroHealthText,
+    HeroPlugin, HeroStatusText, PartyDefinition, PartyHandle, Player,
+};
+use input::InputPlugin;
+use mouse::MousePlugin;
+use popup::PopupPlugin;
+use replay::{Replay, ReplayLog, ReplayPlugin};
+use save::{restore_enemies, restore_run, SavePlugin, SavedRun};
+use target::TargetPlugin;
+use title::TitlePlugin;
+use tower::{start_run, Floor, FloorCleared, TowerDefinition, TowerHandle, TowerPlugin};
+use tween::{tweens_finished, TweenPlugin};
+
+// Everything is laid out in pixels of the native resolution, and the window
+// starts at this multiple of it
+pub const SCALE_FACTOR: f32 = 2.0;
+const UNIT_SIZE: f32 = 32.0;
+
+pub const GAME_WIDTH: f32 = UNIT_SIZE * 16.0;
+pub const GAME_HEIGHT: f32 = UNIT_SIZE * 9.0;
+
+// Enemy display
+const ENEMY_DISPLAY_TRANSLATION: Vec3 = Vec3::new(0.0, GAME_HEIGHT * 0.25, 0.0);
+const ENEMY_COL_WIDTH: f32 = GAME_WIDTH / 4.0;
+const ENEMY_SPRITE_SIZE: f32 = 64.0;
+const MAX_ENEMIES: usize = 4;
+
+// Player display
+const HERO_COL_WIDTH: f32 = GAME_WIDTH / 6.0;
+const HERO_SPRITE_SIZE: f32 = 32.0;
+const PLAYER_DISPLAY_TRANSLATION: Vec3 = Vec3::new(0.0, -(GAME_HEIGHT * 0.25), 0.0);
+
+// Menu
+const MENU_BLOCK: f32 = UNIT_SIZE * 0.75;
+
+// Cards
+const CARD_COL_WIDTH: f32 = UNIT_SIZE * 1.5;
+const CARD_SIZE: Vec2 = Vec2::new(40.0, 56.0);
+
+// Mouse
+const HOVER_COLOR: Color = Color::srgb(1.0, 1.0, 0.6);
+
+// Battle info
+const BATTLE_INFO_START_OFFSET: f32 = MENU_BLOCK * 2.0;
+
+#[derive(Debug, Deref, Event)]
+pub struct ActionEvent(pub Action);
+
+// What an action did, sent once the rules have run
+#[derive(Debug, Deref, Event)]
+pub struct OutcomeEvent(pub BattleOutcome);
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, States)]
+pub enum AppState {
+    #[default]
+    Loading,
+    GameOver,
+    Run,
+    Title,
+    Victory,
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, SubStates)]
+#[source(AppState = AppState::Run)]
+pub enum InfoPanelState {
+    Battle,
+    #[default]
+    Menu,
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, SubStates)]
+#[source(InfoPanelState = InfoPanelState::Menu)]
+pub enum MenuState {
+    #[default]
+    Action,
+    Target,
+}
+
+#[derive(Component)]
+struct InfoPanelNode;
+
+#[derive(Component)]
+struct InfoPanelTarget;
+
+#[derive(Component)]
+struct BattleInfoText;
+
+#[derive(Default, Resource)]
+struct BattleInfoTimer(Option<Timer>);
+
+#[derive(Clone, Copy, Debug, Resource)]
+pub struct Persistent(pub bool);
+
+pub fn is_persistent(persistent: Res<Persistent>) -> bool {
+    persistent.0
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, SubStates)]
+#[source(AppState = AppState::Run)]
+pub enum BattleState {
+    Enemy,
+    Lose,
+    #[default]
+    Player,
+    Win,
+}
+
+fn check_loading(
+    asset_server: Res<AssetServer>,
+    mut next_state: ResMut<NextState<AppState>>,
+    party_handle: Res<PartyHandle>,
+    replay: Option<Res<Replay>>,
+    mut saved_run: ResMut<SavedRun>,
+    tower_handle: Res<TowerHandle>,
+) {
+    if asset_server.is_loaded_with_dependencies(&party_handle.0)
+        && asset_server.is_loaded_with_dependencies(&tower_handle.0)
+    {
+        // Replays always start from scratch, and straight away
+        if replay.is_some() {
+            saved_run.0 = None;
+            next_state.set(AppState::Run);
+            return;
+        }
+
+        next_state.set(AppState::Title);
+    }
+}
+
+// Anything that failed to load, which would otherwise leave the game waiting on
+// it forever
+#[derive(Default, Resource)]
+struct LoadErrors(Vec<String>);
+
+#[derive(Component)]
+struct LoadErrorText;
+
+fn collect_load_errors<A: Asset>(
+    mut failed_reader: EventReader<AssetLoadFailedEvent<A>>,
+    mut load_errors: ResMut<LoadErrors>,
+) {
+    for event in failed_reader.read() {
+        // The error already names the file
+        let message = event.error.to_string();
+
+        error!("{}", message);
+        load_errors.0.push(message);
+    }
+}
+
+fn show_load_errors(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    load_errors: Res<LoadErrors>,
+    mut text_query: Query<&mut Text, With<LoadErrorText>>,
+) {
+    if load_errors.0.is_empty() {
+        return;
+    }
+
+    let message = load_errors.0.join("\n");
+
+    if let Ok(mut text) = text_query.get_single_mut() {
+        text.sections[0].value = message;
+        return;
+    }
+
+    commands.spawn((
+        LoadErrorText,
+        StateScoped(AppState::Loading),
+        Text2dBundle {
+            text: Text::from_section(
+                message,
+                TextStyle {
+                    font: asset_server.load("fonts/press_start_2p.ttf"),
+                    font_size: 8.0,
+                    ..default()
+                },
+            ),
+            text_2d_bounds: Text2dBounds {
+                size: Vec2::new(GAME_WIDTH - UNIT_SIZE, GAME_HEIGHT - UNIT_SIZE),
+            },
+            ..default()
+        },
+    ));
+}
+
+fn setup_scene(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    hero_definitions: HeroDefinitions,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    // Font handle
+
+    let font_handle = asset_server.load("fonts/press_start_2p.ttf");
+
+    // Textures
+
+    let basic_background_texture_handle = asset_server.load("basic_background.png");
+    let heroes_texture_handle = asset_server.load("heroes.png");
+    let heroes_layout_handle = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
+        UVec2::splat(HERO_SPRITE_SIZE as u32),
+        2,
+        3,
+        None,
+        None,
+    ));
+
+    // Enemy display
+
+    commands
+        .spawn((
+            EnemyDisplay,
+            StateScoped(AppState::Run),
+            SpatialBundle {
+                transform: Transform {
+                    translation: ENEMY_DISPLAY_TRANSLATION,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            // Background
+
+            parent.spawn(SpriteBundle {
+                texture: asset_server.load("enemy_background.png"),
+                transform: Transform {
+                    translation: Vec3::new(0.0, 0.0, -1.0),
+                    ..default()
+                },
+                ..default()
+            });
+        });
+
+    // Player display
+
+    commands
+        .spawn((
+            StateScoped(AppState::Run),
+            SpatialBundle {
+                transform: Transform {
+                    translation: PLAYER_DISPLAY_TRANSLATION,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            // Background sprite info
+            let scale_mode = ImageScaleMode::Sliced(TextureSlicer {
+                border: BorderRect::square(4.0),
+                center_scale_mode: SliceScaleMode::Stretch,
+                sides_scale_mode: SliceScaleMode::Stretch,
+                max_corner_scale: 2.0,
+            });
+            let size = Some(Vec2::new(GAME_WIDTH * 0.5, GAME_HEIGHT * 0.5));
+
+            // Hero pane
+
+            parent
+                .spawn(SpatialBundle {
+                    transform: Transform {
+                        translation: Vec3::new(-(GAME_WIDTH * 0.25), 0.0, 0.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    // Background
+
+                    parent.spawn((
+                        scale_mode.clone(),
+                        SpriteBundle {
+                            sprite: Sprite {
+                                custom_size: size,
+                                ..default()
+                            },
+                            texture: basic_background_texture_handle.clone(),
+                            transform: Transform {
+                                translation: Vec3::new(0.0, 0.0, -1.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ));
+
+                    // Heroes
+                    let hero_count = hero_definitions.iter().count();
+
+                    for (i, definition) in hero_definitions.iter().enumerate() {
+                        let x_offset = hero_offset(i, hero_count);
+
+                        parent
+                            .spawn((
+                                Hero {
+                                    index: i,
+                                    sprite_index: definition.sprite_index,
+                                },
+                                SpriteBundle {
+                                    texture: heroes_texture_handle.clone(),
+                                    transform: Transform {
+                                        translation: Vec3::new(x_offset, 0.0, 0.0),
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                TextureAtlas {
+                                    index: definition.sprite_index,
+                                    layout: heroes_layout_handle.clone(),
+                                },
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    HeroStatusText(i),
+                                    Text2dBundle {
+                                        text: Text::from_section(
+                                            "HeroStatusText",
+                                            TextStyle {
+                                                color: Color::NONE,
+                                                font: font_handle.clone(),
+                                                font_size: 8.0,
+                                            },
+                                        ),
+                                        ..default()
+                                    },
+                                ));
+
+                                spawn_health_bar(
+                                    &asset_server,
+                                    parent,
+                                    Target::Hero(i),
+                                    Vec3::new(
+                                        0.0,
+                                        -(HERO_SPRITE_SIZE * 0.5 + BAR_HEIGHT * 0.75),
+                                        0.0,
+                                    ),
+                                    HERO_SPRITE_SIZE * 1.5,
+                                );
+
+                                parent.spawn((
+                                    HeroHealthText(i),
+                                    Text2dBundle {
+                                        text: Text::from_section(
+                                            "HeroHealthText",
+                                            TextStyle {
+                                                font: font_handle.clone(),
+                                                font_size: 8.0,
+                                                ..default()
+                                            },
+                                        ),
+                                        transform: Transform {
+                                            translation: Vec3::new(0.0, -HERO_SPRITE_SIZE, 0.0),
+                                            ..default()
+                                        },
+                                        ..default()
+                                    },
+                                ));
+                            });
+                    }
+
+                    // Hero arrow
+                    parent.spawn((
+                        HeroArrow,
+                        SpriteBundle {
+                            texture: asset_server.load("arrow_down.png"),
+                            transform: Transform {
+                                translation: Vec3::new(0.0, HERO_SPRITE_SIZE * 0.75, 0.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ));
+                });
+
+            // Command/menu pane
+
+            parent
+                .spawn((
+                    InfoPanelTarget,
+                    SpatialBundle {
+                        transform: Transform {
+                            translation: Vec3::new(GAME_WIDTH * 0.25, 0.0, 0.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    // Background
+
+                    parent.spawn((
+                        scale_mode.clone(),
+                        SpriteBundle {
+                            sprite: Sprite {
+                                custom_size: size,
+                                ..default()
+                            },
+                            texture: basic_background_texture_handle.clone(),
+                            transform: Transform {
+                                translation: Vec3::new(0.0, 0.0, -1.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+// Whatever the last run left behind
+fn reset_run(
+    mut battle_info_timer: ResMut<BattleInfoTimer>,
+    mut enemy_turn: ResMut<EnemyTurn>,
+    mut hand_selection: ResMut<HandSelection>,
+) {
+    *battle_info_timer = BattleInfoTimer::default();
+    *enemy_turn = EnemyTurn::default();
+    *hand_selection = HandSelection::default();
+}
+
+fn cleanup_info_panel(mut commands: Commands, node_query: Query<Entity, With<InfoPanelNode>>) {
+    for entity_id in node_query.iter() {
+        commands.entity(entity_id).despawn_recursive();
+    }
+}
+
+fn setup_info(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    target_query: Query<Entity, With<InfoPanelTarget>>,
+) {
+    let font_handle = asset_server.load("fonts/press_start_2p.ttf");
+
+    let target_entity_id = match target_query.get_single() {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    commands
+        .get_entity(target_entity_id)
+        .unwrap()
+        .with_children(|parent| {
+            parent
+                .spawn((InfoPanelNode, SpatialBundle::default()))
+                .with_children(|parent| {
+                    // Info text
+
+                    parent.spawn((
+                        BattleInfoText,
+                        Text2dBundle {
+                            text: Text {
+                                linebreak_behavior: bevy::text::BreakLineOn::WordBoundary,
+                                justify: JustifyText::Left,
+                                sections: vec![TextSection::new(
+                                    "",
+                                    TextStyle {
+                                        font: font_handle.clone(),
+                                        font_size: 8.0,
+                                        ..default()
+                                    },
+                                )],
+                            },
+                            text_2d_bounds: Text2dBounds {
+                                size: Vec2::new(UNIT_SIZE * 6.0, UNIT_SIZE * 9.0),
+                            },
+                            text_anchor: Anchor::TopLeft,
+                            transform: Transform {
+                                translation: Vec3::new(
+                                    -(UNIT_SIZE * 3.0),
+                                    BATTLE_INFO_START_OFFSET,
+                                    0.0,
+                                ),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+fn target_name(battle: &battle::BattleState, target: Target) -> &str {
+    match target {
+        Target::Enemy(enemy) => &battle.enemies[enemy].name,
+        Target::Hero(hero) => &battle.party.heroes[hero].name,
+    }
+}
+
+fn battle_message(
+    action: &Action,
+    outcomes: &[BattleOutcome],
+    battle: &battle::BattleState,
+) -> String {
+    let mut lines = match *action {
+        Action::Enemy { enemy, attack, .. } => {
+            let enemy = &battle.enemies[enemy];
+
+            vec![format!(
+                "{} uses {}!",
+                enemy.name, enemy.attacks[attack].name
+            )]
+        }
+        Action::Hero { hero, action, .. } => {
+            let hero = &battle.party.heroes[hero];
+
+            vec![format!("{} uses {}!", hero.name, hero.actions[action].name)]
+        }
+        Action::Rest { hero } => vec![format!("{} rests.", battle.party.heroes[hero].name)],
+        Action::EndRound => vec![],
+    };
+
+    for outcome in outcomes.iter() {
+        let line = match *outcome {
+            BattleOutcome::Absorbed {
+                target,
+                kind,
+                amount,
+            } => format!(
+                "{}'s {} stops {} damage!",
+                target_name(battle, target),
+                kind.label().to_lowercase(),
+                amount
+            ),
+            BattleOutcome::Damaged { target, amount } => {
+                format!("{} takes {} damage!", target_name(battle, target), amount)
+            }
+            BattleOutcome::Defeated { target } => {
+                format!("{} falls!", target_name(battle, target))
+            }
+            BattleOutcome::HealFailed { target } => format!(
+                "{} cannot be raised from the dead!",
+                target_name(battle, target)
+            ),
+            BattleOutcome::Healed { target, amount } => {
+                format!("{} heals for {}!", target_name(battle, target), amount)
+            }
+            BattleOutcome::StatusApplied { target, kind } => {
+                format!(
+                    "{} {}!",
+                    target_name(battle, target),
+                    kind.definition().message
+                )
+            }
+            BattleOutcome::Lost | BattleOutcome::Won => continue,
+        };
+
+        lines.push(line);
+    }
+
+    lines.join(" ")
+}
+
+fn handle_event(
+    mut action_event_reader: EventReader<ActionEvent>,
+    mut battle_info_timer: ResMut<BattleInfoTimer>,
+    mut battle_log: ResMut<BattleLog>,
+    mut enemy_query: Query<&mut Enemy>,
+    mut next_state: ResMut<NextState<BattleState>>,
+    mut outcome_event_writer: EventWriter<OutcomeEvent>,
+    mut player: ResMut<Player>,
+    mut info_text_query: Query<&mut Text, With<BattleInfoText>>,
+) {
+    for event in action_event_reader.read() {
+        let mut enemies = enemy_query.iter_mut().collect::<Vec<_>>();
+
+        enemies.sort_by_key(|enemy| enemy.index);
+
+        // Run the rules against a copy of the ECS state, then write it back
+
+        let mut battle = battle::BattleState::new(
+            player.0.clone(),
+            enemies.iter().map(|enemy| enemy.state.clone()).collect(),
+        );
+        let outcomes = battle.apply(event);
+        let message = battle_message(event, &outcomes, &battle);
+
+        player.0 = battle.party;
+        for (enemy, state) in enemies.iter_mut().zip(battle.enemies) {
+            enemy.state = state;
+        }
+
+        for outcome in outcomes.iter() {
+            match outcome {
+                BattleOutcome::Lost => next_state.set(BattleState::Lose),
+                BattleOutcome::Won => next_state.set(BattleState::Win),
+                _ => {}
+            }
+        }
+
+        outcome_event_writer.send_batch(outcomes.into_iter().map(OutcomeEvent));
+
+        // A quiet end of round moves straight on, keeping the last message up
+        let delay = if message.is_empty() {
+            Duration::ZERO
+        } else {
+            Duration::from_secs(3)
+        };
+
+        if !message.is_empty() {
+            for mut text in info_text_query.iter_mut() {
+                text.sections[0].value = message.clone();
+            }
+
+            battle_log.push(message);
+        }
+
+        if let Action::EndRound = **event {
+            battle_log.end_round();
+        }
+
+        battle_info_timer.0 = Some(Timer::new(delay, TimerMode::Once));
+    }
+}
+
+fn tick_battle_info_timer(
+    mut battle_info_timer: ResMut<BattleInfoTimer>,
+    mut enemy_act_writer: EventWriter<EnemyAct>,
+    floor: Res<Floor>,
+    mut floor_cleared_writer: EventWriter<FloorCleared>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_battle_state: ResMut<NextState<BattleState>>,
+    state: Res<State<BattleState>>,
+    time: Res<Time>,
+) {
+    let Some(timer) = battle_info_timer.0.as_mut() else {
+        return;
+    };
+
+    timer.tick(time.delta());
+
+    if timer.just_finished() {
+        match state.into_inner().get() {
+            // The next enemy, the end of the round or back to the heroes
+            BattleState::Enemy => {
+                enemy_act_writer.send(EnemyAct);
+            }
+            BattleState::Player => {
+                next_battle_state.set(BattleState::Enemy);
+            }
+            BattleState::Lose => {
+                next_app_state.set(AppState::GameOver);
+            }
+            BattleState::Win => {
+                if !floor.is_top() {
+                    floor_cleared_writer.send(FloorCleared);
+                    return;
+                }
+
+                next_app_state.set(AppState::Victory);
+            }
+        }
+    }
+}
+
+// Everything the game needs besides a window and a renderer, so it also runs
+// under `MinimalPlugins`
+pub struct GamePlugin {
+    // Whether saves, replays and bindings are read from and written to disk
+    pub is_persistent: bool,
+    pub replay: Option<ReplayLog>,
+    pub seed: u64,
+}
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(replay_log) = &self.replay {
+            app.insert_resource(Replay::new(replay_log.clone()));
+        }
+
+        app.add_event::<ActionEvent>()
+            .add_event::<OutcomeEvent>()
+            .add_plugins((
+                BattleLogPlugin,
+                CanvasPlugin,
+                CardPlugin,
+                EnemyAiPlugin,
+                EnemyPlugin,
+                EntropyPlugin::<WyRand>::with_seed(self.seed.to_le_bytes()),
+                GameAudioPlugin,
+                HealthBarPlugin,
+                HeroPlugin,
+                InputPlugin,
+                MousePlugin,
+            ))
+            .add_plugins((
+                PopupPlugin,
+                ReplayPlugin,
+                SavePlugin,
+                TargetPlugin,
+                TitlePlugin,
+                TowerPlugin,
+                TweenPlugin,
+            ))
+            .add_systems(
+                OnEnter(AppState::Run),
+                (
+                    reset_run,
+                    seed_run,
+                    insert_player,
+                    insert_deck,
+                    start_run,
+                    restore_run,
+                    setup_scene,
+                    spawn_enemy,
+                    restore_enemies,
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(InfoPanelState::Battle), setup_info)
+            .add_systems(OnExit(InfoPanelState::Battle), cleanup_info_panel)
+            .add_systems(OnExit(InfoPanelState::Menu), cleanup_info_panel)
+            .add_systems(
+                Update,
+                (
+                    (
+                        check_loading,
+                        (
+                            collect_load_errors::<EnemyDefinition>,
+                            collect_load_errors::<HeroDefinition>,
+                            collect_load_errors::<PartyDefinition>,
+                            collect_load_errors::<TowerDefinition>,
+                        ),
+                        show_load_errors.run_if(resource_changed::<LoadErrors>),
+                    )
+                        .chain()
+                        .run_if(in_state(AppState::Loading)),
+                    (
+                        handle_event,
+                        // Nothing moves on until the last action has finished playing out
+                        tick_battle_info_timer.run_if(tweens_finished),
+                    )
+                        .run_if(in_state(InfoPanelState::Battle)),
+                ),
+            )
+            .init_resource::<BattleInfoTimer>()
+            .init_resource::<LoadErrors>()
+            .insert_resource(Persistent(self.is_persistent))
+            .insert_resource(RunSeed(self.seed))
+            .init_state::<AppState>()
+            .add_sub_state::<BattleState>()
+            .add_sub_state::<InfoPanelState>()
+            .add_sub_state::<MenuState>()
+            .enable_state_scoped_entities::<AppState>();
+    }
+}
@@ -1,15 +1,32 @@
 use bevy::prelude::*;
 
+use crate::canvas::ScreenCamera;
+
 #[derive(Debug, Default, Resource)]
 pub struct MousePosition(pub Vec2);
 
+impl MousePosition {
+    // Hit test against a box of `size` centred on `center`, in world space
+    pub fn is_over(&self, center: Vec2, size: Vec2) -> bool {
+        let offset = (self.0 - center).abs();
+
+        offset.x <= size.x * 0.5 && offset.y <= size.y * 0.5
+    }
+}
+
 fn mouse_position(
-    camera_query: Query<(&Camera, &GlobalTransform)>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<ScreenCamera>>,
     mut mouse_position: ResMut<MousePosition>,
     window_query: Query<&Window>,
 ) {
-    let (camera, camera_transform) = camera_query.single();
-    let window = window_query.single();
+    // Neither exists when running headless
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
 
     let Some(cursor_position) = window.cursor_position() else {
         return;
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    battle::{BattleOutcome, Target},
+    enemy::Enemy,
+    hero::Hero,
+    status::StatusKind,
+    AppState, OutcomeEvent, UNIT_SIZE,
+};
+
+const POPUP_SECONDS: f32 = 1.0;
+// How far a number drifts up over its life
+const POPUP_RISE: f32 = UNIT_SIZE;
+// Numbers landing on the same target at once are stacked this far apart
+const POPUP_SPACING: f32 = 10.0;
+
+const DAMAGE_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+const HEAL_COLOR: Color = Color::srgb(0.4, 1.0, 0.4);
+const BLOCK_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
+const SHIELD_COLOR: Color = Color::srgb(0.4, 0.8, 1.0);
+
+#[derive(Component)]
+struct Popup {
+    start: Vec3,
+    timer: Timer,
+}
+
+// The number and color to show for an outcome, if it has one
+fn popup_text(outcome: &BattleOutcome) -> Option<(Target, String, Color)> {
+    match *outcome {
+        BattleOutcome::Absorbed {
+            target,
+            kind,
+            amount,
+        } => {
+            let color = match kind {
+                StatusKind::Shield => SHIELD_COLOR,
+                _ => BLOCK_COLOR,
+            };
+
+            Some((target, amount.to_string(), color))
+        }
+        BattleOutcome::Damaged { target, amount } => {
+            Some((target, format!("-{amount}"), DAMAGE_COLOR))
+        }
+        BattleOutcome::Healed { target, amount } => {
+            Some((target, format!("+{amount}"), HEAL_COLOR))
+        }
+        _ => None,
+    }
+}
+
+fn spawn_popups(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    enemy_query: Query<(&Enemy, &GlobalTransform)>,
+    hero_query: Query<(&Hero, &GlobalTransform)>,
+    mut outcome_event_reader: EventReader<OutcomeEvent>,
+) {
+    let font_handle = asset_server.load("fonts/press_start_2p.ttf");
+
+    let mut counts = HashMap::<Target, usize>::new();
+
+    for event in outcome_event_reader.read() {
+        // A hit that was soaked up entirely already shows what stopped it
+        if let BattleOutcome::Damaged { amount: 0, target } = **event {
+            if counts.contains_key(&target) {
+                continue;
+            }
+        }
+
+        let Some((target, value, color)) = popup_text(event) else {
+            continue;
+        };
+
+        let position = match target {
+            Target::Enemy(index) => enemy_query
+                .iter()
+                .find(|(enemy, _)| enemy.index == index)
+                .map(|(_, transform)| transform.translation()),
+            Target::Hero(index) => hero_query
+                .iter()
+                .find(|(hero, _)| hero.index == index)
+                .map(|(_, transform)| transform.translation()),
+        };
+
+        let Some(position) = position else {
+            continue;
+        };
+
+        let count = counts.entry(target).or_default();
+        let start = position + Vec3::new(0.0, *count as f32 * POPUP_SPACING, 20.0);
+
+        *count += 1;
+
+        commands.spawn((
+            Popup {
+                start,
+                timer: Timer::from_seconds(POPUP_SECONDS, TimerMode::Once),
+            },
+            StateScoped(AppState::Run),
+            Text2dBundle {
+                text: Text::from_section(
+                    value,
+                    TextStyle {
+                        color,
+                        font: font_handle.clone(),
+                        font_size: 8.0,
+                    },
+                ),
+                transform: Transform::from_translation(start),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn animate_popups(
+    mut commands: Commands,
+    mut popup_query: Query<(Entity, &mut Popup, &mut Text, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (entity_id, mut popup, mut text, mut transform) in popup_query.iter_mut() {
+        popup.timer.tick(time.delta());
+
+        if popup.timer.finished() {
+            commands.entity(entity_id).despawn();
+            continue;
+        }
+
+        let progress = popup.timer.fraction();
+
+        // Whole pixels keep the text crisp on the canvas
+        transform.translation.y = (popup.start.y + POPUP_RISE * progress).round();
+
+        // Fades out over the second half
+        let alpha = (2.0 - progress * 2.0).min(1.0);
+
+        for section in text.sections.iter_mut() {
+            section.style.color.set_alpha(alpha);
+        }
+    }
+}
+
+pub struct PopupPlugin;
+
+impl Plugin for PopupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (spawn_popups, animate_popups).run_if(in_state(AppState::Run)),
+        );
+    }
+}
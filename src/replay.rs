@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::RunSeed, battle::Action, hero::Player, input::GameInputSet, save::restore_run, ActionEvent,
+    AppState, InfoPanelState, MenuState, Persistent,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+const REPLAY_PATH: &str = "replay.ron";
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum ReplayEntry {
+    // The hero the player had cycled to when choosing
+    Hero(usize),
+    Action(Action),
+}
+
+// The seed and every choice the player has made this run
+#[derive(Clone, Debug, Default, Deserialize, Resource, Serialize)]
+pub struct ReplayLog {
+    pub entries: Vec<ReplayEntry>,
+    pub seed: u64,
+}
+
+impl ReplayLog {
+    // Reads the file given with `--replay <file>`, if any
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_args() -> Option<Self> {
+        let path = std::env::args()
+            .skip_while(|arg| arg != "--replay")
+            .nth(1)?;
+
+        let result = std::fs::read_to_string(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                ron::from_str::<ReplayLog>(&contents).map_err(|error| error.to_string())
+            });
+
+        match result {
+            Ok(replay_log) => Some(replay_log),
+            Err(error) => {
+                error!("Could not read replay {}: {}", path, error);
+                None
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_args() -> Option<Self> {
+        None
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write(&self) {
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                std::fs::write(REPLAY_PATH, contents).map_err(|error| error.to_string())
+            });
+
+        if let Err(error) = result {
+            warn!("Could not write {}: {}", REPLAY_PATH, error);
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn write(&self) {}
+}
+
+// Choices still to be played back, only present when replaying
+#[derive(Resource)]
+pub struct Replay {
+    entries: VecDeque<ReplayEntry>,
+}
+
+impl Replay {
+    pub fn new(replay_log: ReplayLog) -> Self {
+        Self {
+            entries: replay_log.entries.into(),
+        }
+    }
+}
+
+pub fn is_replaying(replay: Option<Res<Replay>>) -> bool {
+    replay.is_some()
+}
+
+fn is_playing_back(replay: Option<Res<Replay>>) -> bool {
+    replay.is_some_and(|replay| !replay.entries.is_empty())
+}
+
+fn begin_recording(mut replay_log: ResMut<ReplayLog>, seed: Res<RunSeed>) {
+    replay_log.entries.clear();
+    replay_log.seed = seed.0;
+}
+
+// Once the replayed run is over, the next one is the player's own
+fn end_replay(mut commands: Commands) {
+    commands.remove_resource::<Replay>();
+}
+
+fn record_actions(
+    mut action_event_reader: EventReader<ActionEvent>,
+    persistent: Res<Persistent>,
+    player: Res<Player>,
+    mut replay_log: ResMut<ReplayLog>,
+) {
+    let mut is_changed = false;
+
+    for event in action_event_reader.read() {
+        // Enemy turns follow from the seed, so only the player's choices matter
+        if let Action::Hero { .. } | Action::Rest { .. } = **event {
+            replay_log
+                .entries
+                .push(ReplayEntry::Hero(player.current_hero));
+            replay_log.entries.push(ReplayEntry::Action(**event));
+            is_changed = true;
+        }
+    }
+
+    if is_changed && persistent.0 {
+        replay_log.write();
+    }
+}
+
+fn play_back(
+    mut action_event_writer: EventWriter<ActionEvent>,
+    mut next_state: ResMut<NextState<InfoPanelState>>,
+    mut player: ResMut<Player>,
+    mut replay: ResMut<Replay>,
+) {
+    while let Some(entry) = replay.entries.pop_front() {
+        match entry {
+            ReplayEntry::Hero(hero) => player.current_hero = hero,
+            ReplayEntry::Action(action) => {
+                next_state.set(InfoPanelState::Battle);
+                action_event_writer.send(ActionEvent(action));
+                break;
+            }
+        }
+    }
+
+    if replay.entries.is_empty() {
+        info!("Replay finished, handing over to the player");
+    }
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayLog>()
+            .configure_sets(Update, GameInputSet.run_if(not(is_playing_back)))
+            .add_systems(OnEnter(AppState::Run), begin_recording.before(restore_run))
+            .add_systems(OnExit(AppState::Run), end_replay)
+            .add_systems(
+                Update,
+                (
+                    record_actions
+                        .after(GameInputSet)
+                        .run_if(not(is_replaying))
+                        .run_if(in_state(AppState::Run)),
+                    play_back
+                        .run_if(is_playing_back)
+                        .run_if(in_state(MenuState::Action)),
+                ),
+            );
+    }
+}
@@ -0,0 +1,262 @@
+use bevy::prelude::*;
+use bevy_prng::WyRand;
+use bevy_rand::prelude::{EntropyComponent, GlobalEntropy};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::RunSeed,
+    battle::{DeckState, EnemyState, PartyState},
+    card::{draw_hand, Deck},
+    enemy::{Encounter, Enemy},
+    hero::Player,
+    is_persistent,
+    replay::{is_replaying, ReplayEntry, ReplayLog},
+    tower::{Floor, TowerDefinition, TowerHandle},
+    BattleState,
+};
+
+const SAVE_PATH: &str = "save.ron";
+
+#[derive(Deserialize, Serialize)]
+struct SavedEnemy {
+    rng: EntropyComponent<WyRand>,
+    state: EnemyState,
+}
+
+// Everything needed to pick a run back up as the heroes' turn starts. Only
+// written then, so leaving mid-turn goes back to the start of that turn
+#[derive(Deserialize, Serialize)]
+pub struct SaveData {
+    deck: DeckState,
+    deck_rng: EntropyComponent<WyRand>,
+    enemies: Vec<SavedEnemy>,
+    floor: usize,
+    global_rng: GlobalEntropy<WyRand>,
+    party: PartyState,
+    #[serde(default)]
+    replay: Vec<ReplayEntry>,
+    seed: u64,
+}
+
+impl SaveData {
+    fn read() -> Option<Self> {
+        let contents = read_stored(SAVE_PATH)?;
+
+        match ron::from_str::<SaveData>(&contents) {
+            Ok(save) => Some(save),
+            Err(error) => {
+                warn!("Could not read saved run: {}", error);
+                None
+            }
+        }
+    }
+
+    fn write(&self) {
+        let result = ron::to_string(self)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| write_stored(SAVE_PATH, &contents));
+
+        if let Err(error) = result {
+            warn!("Could not write {}: {}", SAVE_PATH, error);
+        }
+    }
+
+    pub fn delete() {
+        if let Err(error) = remove_stored(SAVE_PATH) {
+            warn!("Could not remove {}: {}", SAVE_PATH, error);
+        }
+    }
+}
+
+// Saves and settings live in files next to the game, or in localStorage on
+// the web under the same name
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_stored(name: &str) -> Option<String> {
+    std::fs::read_to_string(name).ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read_stored(name: &str) -> Option<String> {
+    local_storage()?.get_item(&storage_key(name)).ok()?
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_stored(name: &str, contents: &str) -> Result<(), String> {
+    std::fs::write(name, contents).map_err(|error| error.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write_stored(name: &str, contents: &str) -> Result<(), String> {
+    let storage = local_storage().ok_or("no local storage")?;
+
+    storage
+        .set_item(&storage_key(name), contents)
+        .map_err(|error| format!("{error:?}"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn remove_stored(name: &str) -> Result<(), String> {
+    match std::fs::remove_file(name) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn remove_stored(name: &str) -> Result<(), String> {
+    let Some(storage) = local_storage() else {
+        return Ok(());
+    };
+
+    storage
+        .remove_item(&storage_key(name))
+        .map_err(|error| format!("{error:?}"))
+}
+
+// Keeps runs saved before settings were stored under "cycle_the_heroes.save"
+#[cfg(target_arch = "wasm32")]
+fn storage_key(name: &str) -> String {
+    format!("cycle_the_heroes.{}", name.trim_end_matches(".ron"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+// The run found on disk at startup, until it is resumed or thrown away
+#[derive(Default, Resource)]
+pub struct SavedRun(pub Option<SaveData>);
+
+fn load_saved_run(mut saved_run: ResMut<SavedRun>) {
+    saved_run.0 = SaveData::read();
+}
+
+fn save_run(
+    deck: Res<Deck>,
+    enemy_query: Query<(&Enemy, &EntropyComponent<WyRand>)>,
+    floor: Res<Floor>,
+    global_entropy: Res<GlobalEntropy<WyRand>>,
+    player: Res<Player>,
+    replay_log: Res<ReplayLog>,
+    seed: Res<RunSeed>,
+) {
+    let mut enemies = enemy_query.iter().collect::<Vec<_>>();
+
+    enemies.sort_by_key(|(enemy, _)| enemy.index);
+
+    let save = SaveData {
+        deck: deck.state.clone(),
+        deck_rng: deck.rng.clone(),
+        enemies: enemies
+            .into_iter()
+            .map(|(enemy, rng)| SavedEnemy {
+                rng: rng.clone(),
+                state: enemy.state.clone(),
+            })
+            .collect(),
+        floor: floor.current,
+        global_rng: global_entropy.clone(),
+        party: player.0.clone(),
+        replay: replay_log.entries.clone(),
+        seed: seed.0,
+    };
+
+    save.write();
+}
+
+fn delete_save() {
+    SaveData::delete();
+}
+
+fn is_top_floor(floor: Res<Floor>) -> bool {
+    floor.is_top()
+}
+
+pub fn restore_run(
+    mut deck: ResMut<Deck>,
+    mut encounter: ResMut<Encounter>,
+    mut floor: ResMut<Floor>,
+    mut player: ResMut<Player>,
+    mut replay_log: ResMut<ReplayLog>,
+    mut saved_run: ResMut<SavedRun>,
+    mut seed: ResMut<RunSeed>,
+    tower_definitions: Res<Assets<TowerDefinition>>,
+    tower_handle: Res<TowerHandle>,
+) {
+    let Some(save) = saved_run.0.as_ref() else {
+        return;
+    };
+
+    let Some(tower) = tower_definitions.get(&tower_handle.0) else {
+        return;
+    };
+
+    // The tower may have changed since the run was saved
+    if save.floor >= tower.floors.len() || tower.floors[save.floor].len() != save.enemies.len() {
+        warn!("Saved run no longer fits the tower, starting a new run");
+        saved_run.0 = None;
+        return;
+    }
+
+    deck.rng = save.deck_rng.clone();
+    deck.state = save.deck.clone();
+    encounter.0 = tower.floors[save.floor].clone();
+    floor.current = save.floor;
+    player.0 = save.party.clone();
+    replay_log.entries = save.replay.clone();
+    replay_log.seed = save.seed;
+    seed.0 = save.seed;
+
+    info!("Continuing run with seed {}", seed.0);
+}
+
+// Runs once the enemies exist, and leaves the shared rng as it was saved
+pub fn restore_enemies(
+    mut enemy_query: Query<(&mut Enemy, &mut EntropyComponent<WyRand>)>,
+    mut global_entropy: ResMut<GlobalEntropy<WyRand>>,
+    mut saved_run: ResMut<SavedRun>,
+) {
+    let Some(save) = saved_run.0.take() else {
+        return;
+    };
+
+    for (mut enemy, mut rng) in enemy_query.iter_mut() {
+        let Some(saved) = save.enemies.get(enemy.index) else {
+            continue;
+        };
+
+        enemy.state = saved.state.clone();
+        *rng = saved.rng.clone();
+    }
+
+    *global_entropy = save.global_rng;
+}
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SavedRun>()
+            .add_systems(Startup, load_saved_run.run_if(is_persistent))
+            // Replays leave the player's own run alone
+            .add_systems(
+                OnEnter(BattleState::Player),
+                save_run
+                    .before(draw_hand)
+                    .run_if(is_persistent)
+                    .run_if(not(is_replaying)),
+            )
+            .add_systems(
+                OnEnter(BattleState::Lose),
+                delete_save.run_if(is_persistent).run_if(not(is_replaying)),
+            )
+            .add_systems(
+                OnEnter(BattleState::Win),
+                delete_save
+                    .run_if(is_top_floor)
+                    .run_if(is_persistent)
+                    .run_if(not(is_replaying)),
+            );
+    }
+}
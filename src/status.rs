@@ -0,0 +1,272 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum StatusKind {
+    Block,
+    Poison,
+    Regen,
+    Shield,
+    Sleep,
+    Stun,
+    Weaken,
+}
+
+// How a second dose of a status combines with the one already there
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Stacking {
+    Extend,
+    Intensify,
+    Refresh,
+}
+
+// Everything a status does, looked up by kind. A new status needs a variant
+// and a definition here, nothing else
+pub struct StatusDefinition {
+    pub label: &'static str,
+    // Finishes "<name> ...!" in the battle log when the status lands
+    pub message: &'static str,
+    pub stacking: Stacking,
+    pub prevents_action: bool,
+    // Keeps damage out, so an enemy giving it to itself is defending
+    pub defends: bool,
+    pub on_damage_dealt: fn(&StatusEffect, isize) -> isize,
+    pub on_damage_taken: fn(&StatusEffect, isize) -> isize,
+    pub on_turn_start: fn(&StatusEffect) -> Option<TurnStartEffect>,
+}
+
+fn unchanged(_: &StatusEffect, amount: isize) -> isize {
+    amount
+}
+
+fn reduced(status: &StatusEffect, amount: isize) -> isize {
+    amount - status.amount
+}
+
+fn nothing(_: &StatusEffect) -> Option<TurnStartEffect> {
+    None
+}
+
+const PASSIVE: StatusDefinition = StatusDefinition {
+    label: "",
+    message: "",
+    stacking: Stacking::Refresh,
+    prevents_action: false,
+    defends: false,
+    on_damage_dealt: unchanged,
+    on_damage_taken: unchanged,
+    on_turn_start: nothing,
+};
+
+const BLOCK: StatusDefinition = StatusDefinition {
+    label: "BLOCK",
+    message: "blocks",
+    stacking: Stacking::Intensify,
+    defends: true,
+    on_damage_taken: reduced,
+    ..PASSIVE
+};
+
+const POISON: StatusDefinition = StatusDefinition {
+    label: "POISON",
+    message: "is poisoned",
+    stacking: Stacking::Intensify,
+    on_turn_start: |status| Some(TurnStartEffect::Damage(status.amount)),
+    ..PASSIVE
+};
+
+const REGEN: StatusDefinition = StatusDefinition {
+    label: "REGEN",
+    message: "is regenerating",
+    on_turn_start: |status| Some(TurnStartEffect::Heal(status.amount)),
+    ..PASSIVE
+};
+
+const SHIELD: StatusDefinition = StatusDefinition {
+    label: "SHIELD",
+    message: "is shielded",
+    defends: true,
+    on_damage_taken: reduced,
+    ..PASSIVE
+};
+
+const SLEEP: StatusDefinition = StatusDefinition {
+    label: "SLEEP",
+    message: "falls asleep",
+    prevents_action: true,
+    ..PASSIVE
+};
+
+const STUN: StatusDefinition = StatusDefinition {
+    label: "STUN",
+    message: "is stunned",
+    stacking: Stacking::Extend,
+    prevents_action: true,
+    ..PASSIVE
+};
+
+const WEAKEN: StatusDefinition = StatusDefinition {
+    label: "WEAK",
+    message: "is weakened",
+    on_damage_dealt: reduced,
+    ..PASSIVE
+};
+
+impl StatusKind {
+    pub fn definition(self) -> &'static StatusDefinition {
+        match self {
+            StatusKind::Block => &BLOCK,
+            StatusKind::Poison => &POISON,
+            StatusKind::Regen => &REGEN,
+            StatusKind::Shield => &SHIELD,
+            StatusKind::Sleep => &SLEEP,
+            StatusKind::Stun => &STUN,
+            StatusKind::Weaken => &WEAKEN,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        self.definition().label
+    }
+
+    pub fn stacking(self) -> Stacking {
+        self.definition().stacking
+    }
+
+    pub fn prevents_action(self) -> bool {
+        self.definition().prevents_action
+    }
+}
+
+// What a status does to whoever has it as a round starts
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TurnStartEffect {
+    Damage(isize),
+    Heal(isize),
+}
+
+// `turns` counts down at the end of every round, and 0 lasts until removed
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    #[serde(default)]
+    pub amount: isize,
+    #[serde(default)]
+    pub turns: usize,
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusKind, amount: isize, turns: usize) -> Self {
+        Self {
+            kind,
+            amount,
+            turns,
+        }
+    }
+
+    pub fn on_damage_dealt(&self, amount: isize) -> isize {
+        (self.kind.definition().on_damage_dealt)(self, amount)
+    }
+
+    pub fn on_damage_taken(&self, amount: isize) -> isize {
+        (self.kind.definition().on_damage_taken)(self, amount)
+    }
+
+    pub fn on_turn_start(&self) -> Option<TurnStartEffect> {
+        (self.kind.definition().on_turn_start)(self)
+    }
+
+    // Whether the status is still around afterwards
+    pub fn on_turn_end(&mut self) -> bool {
+        if self.turns == 0 {
+            return true;
+        }
+
+        self.turns -= 1;
+
+        self.turns > 0
+    }
+
+    fn stack(&mut self, other: &StatusEffect) {
+        match self.kind.stacking() {
+            Stacking::Extend => {
+                self.amount = self.amount.max(other.amount);
+                self.turns += other.turns;
+            }
+            Stacking::Intensify => {
+                self.amount += other.amount;
+                self.turns = self.turns.max(other.turns);
+            }
+            Stacking::Refresh => {
+                self.amount = self.amount.max(other.amount);
+                self.turns = self.turns.max(other.turns);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Statuses(Vec<StatusEffect>);
+
+impl Statuses {
+    pub fn apply(&mut self, effect: StatusEffect) {
+        match self.0.iter_mut().find(|status| status.kind == effect.kind) {
+            Some(status) => status.stack(&effect),
+            None => self.0.push(effect),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.0.iter().any(|status| status.kind == kind)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatusEffect> {
+        self.0.iter()
+    }
+
+    pub fn prevents_action(&self) -> bool {
+        self.0.iter().any(|status| status.kind.prevents_action())
+    }
+
+    pub fn remove(&mut self, kind: StatusKind) {
+        self.0.retain(|status| status.kind != kind);
+    }
+
+    pub fn damage_dealt(&self, amount: isize) -> isize {
+        self.0
+            .iter()
+            .fold(amount, |amount, status| status.on_damage_dealt(amount))
+    }
+
+    // What gets through, and how much each status kept out on the way
+    pub fn damage_taken(&self, amount: isize) -> (isize, Vec<(StatusKind, isize)>) {
+        let mut absorbed = Vec::new();
+        let mut remaining = amount;
+
+        for status in self.0.iter() {
+            let next = status.on_damage_taken(remaining);
+
+            if remaining.max(0) > next.max(0) {
+                absorbed.push((status.kind, remaining.max(0) - next.max(0)));
+            }
+
+            remaining = next;
+        }
+
+        (remaining.max(0), absorbed)
+    }
+
+    pub fn turn_start(&self) -> Vec<TurnStartEffect> {
+        self.0
+            .iter()
+            .filter_map(StatusEffect::on_turn_start)
+            .collect()
+    }
+
+    pub fn turn_end(&mut self) {
+        self.0.retain_mut(StatusEffect::on_turn_end);
+    }
+}
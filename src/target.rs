@@ -0,0 +1,241 @@
+use bevy::{prelude::*, window::CursorMoved};
+
+use crate::{
+    battle::{Action, ActionTarget, Target},
+    enemy::Enemy,
+    hero::{Hero, Player},
+    input::{GameInputSet, InputAction, InputActions},
+    mouse::MousePosition,
+    ActionEvent, InfoPanelState, MenuState, ENEMY_SPRITE_SIZE, HERO_SPRITE_SIZE,
+};
+
+// The chosen action and everyone it could be aimed at
+#[derive(Default, Resource)]
+pub struct TargetSelection {
+    pub action: usize,
+    pub candidates: Vec<Target>,
+    pub current: usize,
+}
+
+impl TargetSelection {
+    pub fn target(&self) -> Option<Target> {
+        self.candidates.get(self.current).copied()
+    }
+}
+
+#[derive(Component)]
+struct TargetCursor;
+
+// Where a target is drawn, and the size of its sprite
+fn target_bounds(
+    target: Target,
+    enemy_query: &Query<(&Enemy, &GlobalTransform)>,
+    hero_query: &Query<(&Hero, &GlobalTransform)>,
+) -> Option<(Vec3, f32)> {
+    match target {
+        Target::Enemy(index) => enemy_query
+            .iter()
+            .find(|(enemy, _)| enemy.index == index)
+            .map(|(_, transform)| (transform.translation(), ENEMY_SPRITE_SIZE)),
+        Target::Hero(index) => hero_query
+            .iter()
+            .find(|(hero, _)| hero.index == index)
+            .map(|(_, transform)| (transform.translation(), HERO_SPRITE_SIZE)),
+    }
+}
+
+fn hovered_candidate(
+    enemy_query: &Query<(&Enemy, &GlobalTransform)>,
+    hero_query: &Query<(&Hero, &GlobalTransform)>,
+    mouse_position: &MousePosition,
+    target_selection: &TargetSelection,
+) -> Option<usize> {
+    target_selection.candidates.iter().position(|target| {
+        let Some((translation, size)) = target_bounds(*target, enemy_query, hero_query) else {
+            return false;
+        };
+
+        mouse_position.is_over(translation.truncate(), Vec2::splat(size))
+    })
+}
+
+fn setup_target_selection(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    enemy_query: Query<(&Enemy, &GlobalTransform)>,
+    player: Res<Player>,
+    mut target_selection: ResMut<TargetSelection>,
+) {
+    let target = player.current().actions[target_selection.action].target;
+
+    target_selection.candidates = match target {
+        ActionTarget::Ally => (0..player.heroes.len())
+            .filter(|hero| !player.heroes[*hero].is_dead())
+            .map(Target::Hero)
+            .collect(),
+        ActionTarget::Enemy => {
+            let mut living = enemy_query
+                .iter()
+                .filter(|(enemy, _)| !enemy.is_dead())
+                .map(|(enemy, _)| enemy.index)
+                .collect::<Vec<_>>();
+
+            living.sort();
+
+            living.into_iter().map(Target::Enemy).collect()
+        }
+        ActionTarget::User => vec![Target::Hero(player.current_hero)],
+    };
+
+    // Allies start on the hero acting, enemies on the leftmost
+    target_selection.current = target_selection
+        .candidates
+        .iter()
+        .position(|target| *target == Target::Hero(player.current_hero))
+        .unwrap_or(0);
+
+    commands.spawn((
+        TargetCursor,
+        ImageScaleMode::Sliced(TextureSlicer {
+            border: BorderRect::square(2.0),
+            center_scale_mode: SliceScaleMode::Stretch,
+            sides_scale_mode: SliceScaleMode::Stretch,
+            max_corner_scale: 1.0,
+        }),
+        SpriteBundle {
+            texture: asset_server.load("card_target.png"),
+            ..default()
+        },
+    ));
+}
+
+fn cleanup_target_selection(
+    mut commands: Commands,
+    cursor_query: Query<Entity, With<TargetCursor>>,
+) {
+    for entity_id in cursor_query.iter() {
+        commands.entity(entity_id).despawn_recursive();
+    }
+}
+
+fn target_cursor(
+    mut cursor_query: Query<(&mut Sprite, &mut Transform), With<TargetCursor>>,
+    enemy_query: Query<(&Enemy, &GlobalTransform)>,
+    hero_query: Query<(&Hero, &GlobalTransform)>,
+    target_selection: Res<TargetSelection>,
+) {
+    let Some(target) = target_selection.target() else {
+        return;
+    };
+
+    let Some((translation, size)) = target_bounds(target, &enemy_query, &hero_query) else {
+        return;
+    };
+
+    for (mut sprite, mut transform) in cursor_query.iter_mut() {
+        // Sits just behind the target with a small border showing
+        sprite.custom_size = Some(Vec2::splat(size + 8.0));
+        transform.translation = translation - Vec3::Z * 0.5;
+    }
+}
+
+fn target_change(
+    mut cursor_moved_reader: EventReader<CursorMoved>,
+    enemy_query: Query<(&Enemy, &GlobalTransform)>,
+    hero_query: Query<(&Hero, &GlobalTransform)>,
+    input_actions: Res<InputActions>,
+    mouse_position: Res<MousePosition>,
+    mut target_selection: ResMut<TargetSelection>,
+) {
+    let count = target_selection.candidates.len();
+
+    if count == 0 {
+        return;
+    }
+
+    if input_actions.just_pressed(InputAction::Right)
+        || input_actions.just_pressed(InputAction::Cycle)
+    {
+        target_selection.current = (target_selection.current + 1) % count;
+    }
+
+    if input_actions.just_pressed(InputAction::Left) {
+        target_selection.current = (target_selection.current + count - 1) % count;
+    }
+
+    // Only follow the mouse when it moves, so it doesn't fight the keys
+    if cursor_moved_reader.read().count() > 0 {
+        if let Some(hovered) = hovered_candidate(
+            &enemy_query,
+            &hero_query,
+            &mouse_position,
+            &target_selection,
+        ) {
+            target_selection.current = hovered;
+        }
+    }
+}
+
+fn target_select(
+    mut action_event_writer: EventWriter<ActionEvent>,
+    enemy_query: Query<(&Enemy, &GlobalTransform)>,
+    hero_query: Query<(&Hero, &GlobalTransform)>,
+    input_actions: Res<InputActions>,
+    mouse_position: Res<MousePosition>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut next_state: ResMut<NextState<InfoPanelState>>,
+    player: Res<Player>,
+    target_selection: Res<TargetSelection>,
+) {
+    if input_actions.just_triggered(InputAction::Cancel) {
+        next_menu_state.set(MenuState::Action);
+        return;
+    }
+
+    let target = if input_actions.just_pressed(InputAction::Confirm) {
+        target_selection.target()
+    } else if input_actions.just_clicked(InputAction::Confirm) {
+        hovered_candidate(
+            &enemy_query,
+            &hero_query,
+            &mouse_position,
+            &target_selection,
+        )
+        .map(|hovered| target_selection.candidates[hovered])
+    } else {
+        None
+    };
+
+    let Some(target) = target else {
+        return;
+    };
+
+    let action = Action::Hero {
+        hero: player.current_hero,
+        action: target_selection.action,
+        target,
+    };
+
+    next_state.set(InfoPanelState::Battle);
+    action_event_writer.send(ActionEvent(action));
+}
+
+pub struct TargetPlugin;
+
+impl Plugin for TargetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TargetSelection>()
+            .add_systems(OnEnter(MenuState::Target), setup_target_selection)
+            .add_systems(OnExit(MenuState::Target), cleanup_target_selection)
+            .add_systems(
+                Update,
+                (
+                    target_change.in_set(GameInputSet),
+                    target_cursor,
+                    target_select.in_set(GameInputSet),
+                )
+                    .chain()
+                    .run_if(in_state(MenuState::Target)),
+            );
+    }
+}
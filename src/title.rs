@@ -0,0 +1,279 @@
+use bevy::{
+    prelude::*,
+    sprite::Anchor,
+    text::{Text2dBounds, TextLayoutInfo},
+    window::CursorMoved,
+};
+
+use crate::{
+    input::{InputAction, InputActions},
+    mouse::MousePosition,
+    save::{SaveData, SavedRun},
+    AppState, Persistent, GAME_WIDTH, MENU_BLOCK, UNIT_SIZE,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ScreenOption {
+    Continue,
+    NewRun,
+    Title,
+}
+
+impl ScreenOption {
+    fn label(self) -> &'static str {
+        match self {
+            ScreenOption::Continue => "Continue from turn start",
+            ScreenOption::NewRun => "New run",
+            ScreenOption::Title => "Title",
+        }
+    }
+}
+
+#[derive(Default, Resource)]
+struct ScreenMenu {
+    options: Vec<ScreenOption>,
+    selection: usize,
+}
+
+#[derive(Component)]
+struct ScreenArrow;
+
+#[derive(Component)]
+struct ScreenRow(usize);
+
+fn spawn_screen(
+    asset_server: &AssetServer,
+    commands: &mut Commands,
+    heading: &str,
+    options: Vec<ScreenOption>,
+    screen_menu: &mut ScreenMenu,
+    state: AppState,
+) {
+    let font_handle = asset_server.load("fonts/press_start_2p.ttf");
+
+    let left = -(UNIT_SIZE * 1.5);
+
+    commands
+        .spawn((StateScoped(state), SpatialBundle::default()))
+        .with_children(|parent| {
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(
+                    heading,
+                    TextStyle {
+                        font: font_handle.clone(),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                text_2d_bounds: Text2dBounds {
+                    size: Vec2::new(GAME_WIDTH - UNIT_SIZE, MENU_BLOCK * 2.0),
+                },
+                transform: Transform {
+                    translation: Vec3::new(0.0, MENU_BLOCK * 2.0, 0.0),
+                    ..default()
+                },
+                ..default()
+            });
+
+            for (i, option) in options.iter().enumerate() {
+                parent.spawn((
+                    ScreenRow(i),
+                    Text2dBundle {
+                        text: Text::from_section(
+                            option.label(),
+                            TextStyle {
+                                font: font_handle.clone(),
+                                font_size: 8.0,
+                                ..default()
+                            },
+                        ),
+                        text_anchor: Anchor::CenterLeft,
+                        transform: Transform {
+                            translation: Vec3::new(left, -(i as f32 * MENU_BLOCK), 0.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ));
+            }
+
+            parent.spawn((
+                ScreenArrow,
+                SpriteBundle {
+                    texture: asset_server.load("arrow_right.png"),
+                    transform: Transform {
+                        translation: Vec3::new(left - UNIT_SIZE * 0.5, 0.0, 0.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        });
+
+    *screen_menu = ScreenMenu {
+        options,
+        selection: 0,
+    };
+}
+
+fn setup_title(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    saved_run: Res<SavedRun>,
+    mut screen_menu: ResMut<ScreenMenu>,
+) {
+    // Only offer to continue when there is something to pick up
+    let options = if saved_run.0.is_some() {
+        vec![ScreenOption::Continue, ScreenOption::NewRun]
+    } else {
+        vec![ScreenOption::NewRun]
+    };
+
+    spawn_screen(
+        &asset_server,
+        &mut commands,
+        "Cycle the Heroes",
+        options,
+        &mut screen_menu,
+        AppState::Title,
+    );
+}
+
+fn setup_game_over(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    mut screen_menu: ResMut<ScreenMenu>,
+) {
+    spawn_screen(
+        &asset_server,
+        &mut commands,
+        "You lose!",
+        vec![ScreenOption::NewRun, ScreenOption::Title],
+        &mut screen_menu,
+        AppState::GameOver,
+    );
+}
+
+fn setup_victory(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    mut screen_menu: ResMut<ScreenMenu>,
+) {
+    spawn_screen(
+        &asset_server,
+        &mut commands,
+        "You reached the top of the tower!",
+        vec![ScreenOption::NewRun, ScreenOption::Title],
+        &mut screen_menu,
+        AppState::Victory,
+    );
+}
+
+fn hovered_row(
+    mouse_position: &MousePosition,
+    row_query: &Query<(&ScreenRow, &GlobalTransform, &TextLayoutInfo)>,
+) -> Option<usize> {
+    row_query
+        .iter()
+        .find(|(_, transform, layout)| {
+            // Rows are anchored on their left edge
+            let center = transform.translation().truncate() + Vec2::X * layout.logical_size.x * 0.5;
+
+            mouse_position.is_over(center, layout.logical_size)
+        })
+        .map(|(row, _, _)| row.0)
+}
+
+fn screen_cursor(
+    mut arrow_query: Query<&mut Transform, With<ScreenArrow>>,
+    row_query: Query<(&ScreenRow, &Transform), Without<ScreenArrow>>,
+    screen_menu: Res<ScreenMenu>,
+) {
+    for (row, row_transform) in row_query.iter() {
+        if row.0 != screen_menu.selection {
+            continue;
+        }
+
+        for mut transform in arrow_query.iter_mut() {
+            transform.translation.y = row_transform.translation.y;
+        }
+    }
+}
+
+fn screen_input(
+    mut cursor_moved_reader: EventReader<CursorMoved>,
+    input_actions: Res<InputActions>,
+    mouse_position: Res<MousePosition>,
+    mut next_state: ResMut<NextState<AppState>>,
+    persistent: Res<Persistent>,
+    row_query: Query<(&ScreenRow, &GlobalTransform, &TextLayoutInfo)>,
+    mut saved_run: ResMut<SavedRun>,
+    mut screen_menu: ResMut<ScreenMenu>,
+) {
+    let count = screen_menu.options.len();
+
+    if count == 0 {
+        return;
+    }
+
+    if input_actions.just_pressed(InputAction::Down) {
+        screen_menu.selection = (screen_menu.selection + 1) % count;
+    }
+
+    if input_actions.just_pressed(InputAction::Up) {
+        screen_menu.selection = (screen_menu.selection + count - 1) % count;
+    }
+
+    let hovered = hovered_row(&mouse_position, &row_query);
+
+    // Only follow the mouse when it moves, so it doesn't fight the keys
+    if cursor_moved_reader.read().count() > 0 {
+        if let Some(hovered) = hovered {
+            screen_menu.selection = hovered;
+        }
+    }
+
+    let is_confirmed = input_actions.just_pressed(InputAction::Confirm)
+        || (hovered.is_some() && input_actions.just_clicked(InputAction::Confirm));
+
+    if !is_confirmed {
+        return;
+    }
+
+    match screen_menu.options[screen_menu.selection] {
+        ScreenOption::Continue => next_state.set(AppState::Run),
+        ScreenOption::NewRun => {
+            // Starting over throws the old run away
+            if persistent.0 {
+                SaveData::delete();
+            }
+
+            saved_run.0 = None;
+            next_state.set(AppState::Run);
+        }
+        ScreenOption::Title => next_state.set(AppState::Title),
+    }
+}
+
+fn is_on_screen(state: Res<State<AppState>>) -> bool {
+    matches!(
+        state.get(),
+        AppState::GameOver | AppState::Title | AppState::Victory
+    )
+}
+
+pub struct TitlePlugin;
+
+impl Plugin for TitlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenMenu>()
+            .add_systems(OnEnter(AppState::GameOver), setup_game_over)
+            .add_systems(OnEnter(AppState::Title), setup_title)
+            .add_systems(OnEnter(AppState::Victory), setup_victory)
+            .add_systems(
+                Update,
+                (screen_input, screen_cursor).chain().run_if(is_on_screen),
+            );
+    }
+}
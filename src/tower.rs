@@ -0,0 +1,252 @@
+use bevy::{
+    asset::{
+        io::Reader, AssetLoader, AsyncReadExt, LoadContext, UntypedAssetId, VisitAssetDependencies,
+    },
+    prelude::*,
+    sprite::Anchor,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    enemy::{despawn_enemies, spawn_enemy, Encounter, EnemyDefinition},
+    hero::Player,
+    AppState, BattleState, InfoPanelState, GAME_HEIGHT, GAME_WIDTH, MAX_ENEMIES, UNIT_SIZE,
+};
+
+#[derive(Debug, TypePath)]
+pub struct TowerDefinition {
+    pub floors: Vec<Vec<Handle<EnemyDefinition>>>,
+    // Loaded when the floor is reached rather than with the tower
+    pub music: Vec<String>,
+}
+
+impl TowerDefinition {
+    // Floors past the end of the list keep the last track
+    pub fn music(&self, floor: usize) -> Option<&str> {
+        self.music
+            .get(floor)
+            .or_else(|| self.music.last())
+            .map(String::as_str)
+    }
+}
+
+// The derive only knows about flat lists of handles
+impl VisitAssetDependencies for TowerDefinition {
+    fn visit_dependencies(&self, visit: &mut impl FnMut(UntypedAssetId)) {
+        for handle in self.floors.iter().flatten() {
+            visit(handle.id().untyped());
+        }
+    }
+}
+
+impl Asset for TowerDefinition {}
+
+// Also read by the simulator, which has no asset server
+#[derive(Deserialize)]
+pub struct TowerDefinitionFile {
+    pub floors: Vec<Vec<String>>,
+    #[serde(default)]
+    pub music: Vec<String>,
+}
+
+impl TowerDefinitionFile {
+    pub fn validate(&self) -> Result<(), TowerLoaderError> {
+        if self.floors.is_empty() {
+            return Err(TowerLoaderError::NoFloors);
+        }
+
+        for (i, floor) in self.floors.iter().enumerate() {
+            if floor.is_empty() {
+                return Err(TowerLoaderError::EmptyFloor(i + 1));
+            }
+
+            if floor.len() > MAX_ENEMIES {
+                return Err(TowerLoaderError::CrowdedFloor(i + 1));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum TowerLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("Tower has no floors")]
+    NoFloors,
+    #[error("Floor {0} has no enemies")]
+    EmptyFloor(usize),
+    #[error("Floor {0} has more than {MAX_ENEMIES} enemies")]
+    CrowdedFloor(usize),
+}
+
+#[derive(Default)]
+struct TowerDefinitionLoader;
+
+impl AssetLoader for TowerDefinitionLoader {
+    type Asset = TowerDefinition;
+    type Settings = ();
+    type Error = TowerLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let file = ron::de::from_bytes::<TowerDefinitionFile>(&bytes)?;
+
+        file.validate()?;
+
+        Ok(TowerDefinition {
+            floors: file
+                .floors
+                .into_iter()
+                .map(|floor| {
+                    floor
+                        .into_iter()
+                        .map(|path| load_context.load(path))
+                        .collect()
+                })
+                .collect(),
+            music: file.music,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tower.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct TowerHandle(pub Handle<TowerDefinition>);
+
+#[derive(Resource)]
+pub struct Floor {
+    pub count: usize,
+    pub current: usize,
+}
+
+impl Floor {
+    pub fn is_top(&self) -> bool {
+        self.current + 1 >= self.count
+    }
+}
+
+#[derive(Event)]
+pub struct FloorCleared;
+
+#[derive(Component)]
+struct FloorText;
+
+fn load_tower(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(TowerHandle(asset_server.load("tower.ron")));
+}
+
+pub fn start_run(
+    mut commands: Commands,
+    tower_definitions: Res<Assets<TowerDefinition>>,
+    tower_handle: Res<TowerHandle>,
+) {
+    let Some(tower) = tower_definitions.get(&tower_handle.0) else {
+        return;
+    };
+
+    commands.insert_resource(Floor {
+        count: tower.floors.len(),
+        current: 0,
+    });
+    commands.insert_resource(Encounter(tower.floors[0].clone()));
+}
+
+fn setup_floor_text(asset_server: Res<AssetServer>, mut commands: Commands) {
+    let font_handle = asset_server.load("fonts/press_start_2p.ttf");
+
+    commands.spawn((
+        FloorText,
+        StateScoped(AppState::Run),
+        Text2dBundle {
+            text: Text::from_section(
+                "FloorText",
+                TextStyle {
+                    font: font_handle,
+                    font_size: 8.0,
+                    ..default()
+                },
+            ),
+            text_anchor: Anchor::TopLeft,
+            transform: Transform {
+                translation: Vec3::new(
+                    -(GAME_WIDTH * 0.5) + UNIT_SIZE * 0.25,
+                    (GAME_HEIGHT * 0.5) - UNIT_SIZE * 0.25,
+                    0.0,
+                ),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+}
+
+fn floor_text(floor: Res<Floor>, mut text_query: Query<&mut Text, With<FloorText>>) {
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Floor {}/{}", floor.current + 1, floor.count);
+    }
+}
+
+fn climb(
+    mut encounter: ResMut<Encounter>,
+    mut floor: ResMut<Floor>,
+    mut next_battle_state: ResMut<NextState<BattleState>>,
+    mut next_info_state: ResMut<NextState<InfoPanelState>>,
+    mut player: ResMut<Player>,
+    tower_definitions: Res<Assets<TowerDefinition>>,
+    tower_handle: Res<TowerHandle>,
+) {
+    let Some(tower) = tower_definitions.get(&tower_handle.0) else {
+        return;
+    };
+
+    if floor.is_top() {
+        return;
+    }
+
+    floor.current += 1;
+    encounter.0 = tower.floors[floor.current].clone();
+
+    // Party HP carries over, everything else starts fresh
+    player.start_battle();
+
+    next_battle_state.set(BattleState::Player);
+    next_info_state.set(InfoPanelState::Menu);
+}
+
+pub struct TowerPlugin;
+
+impl Plugin for TowerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FloorCleared>()
+            .init_asset::<TowerDefinition>()
+            .init_asset_loader::<TowerDefinitionLoader>()
+            .add_systems(Startup, load_tower)
+            .add_systems(OnEnter(AppState::Run), setup_floor_text)
+            .add_systems(
+                Update,
+                (
+                    floor_text,
+                    (climb, despawn_enemies, spawn_enemy)
+                        .chain()
+                        .run_if(on_event::<FloorCleared>()),
+                )
+                    .run_if(in_state(AppState::Run)),
+            );
+    }
+}
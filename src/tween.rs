@@ -0,0 +1,208 @@
+use std::f32::consts::PI;
+
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::{
+    battle::{Action, BattleOutcome, Target},
+    enemy::Enemy,
+    hero::Hero,
+    ActionEvent, AppState, OutcomeEvent, UNIT_SIZE,
+};
+
+const LUNGE_SECONDS: f32 = 0.3;
+const LUNGE_DISTANCE: f32 = UNIT_SIZE * 0.5;
+
+const SHAKE_SECONDS: f32 = 0.3;
+const SHAKE_DISTANCE: f32 = 3.0;
+const SHAKES: f32 = 4.0;
+
+const FLASH_SECONDS: f32 = 0.4;
+const FLASH_COLOR: Color = Color::srgb(0.4, 1.0, 0.4);
+
+const FADE_SECONDS: f32 = 0.6;
+// Where a fade ends, and how the fallen stay afterwards
+pub const FADED_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.3);
+
+#[derive(Clone, Copy, Debug)]
+enum TweenKind {
+    // Towards the other side and back
+    Lunge(f32),
+    Shake,
+    Flash,
+    Fade,
+}
+
+impl TweenKind {
+    fn seconds(self) -> f32 {
+        match self {
+            TweenKind::Lunge(_) => LUNGE_SECONDS,
+            TweenKind::Shake => SHAKE_SECONDS,
+            TweenKind::Flash => FLASH_SECONDS,
+            TweenKind::Fade => FADE_SECONDS,
+        }
+    }
+}
+
+// A short animation on a hero or enemy sprite, removed once it has played
+#[derive(Component, Debug)]
+pub struct Tween {
+    // Waits for the attacker's lunge before the target reacts
+    delay: f32,
+    elapsed: f32,
+    kind: TweenKind,
+    origin: Vec3,
+}
+
+impl Tween {
+    fn progress(&self) -> f32 {
+        ((self.elapsed - self.delay) / self.kind.seconds()).clamp(0.0, 1.0)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.delay + self.kind.seconds()
+    }
+}
+
+// Starts and plays tweens. Anything else setting sprite colors runs after it,
+// and leaves tweening sprites alone
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SystemSet)]
+pub struct TweenSystem;
+
+pub fn tweens_finished(tween_query: Query<(), With<Tween>>) -> bool {
+    tween_query.is_empty()
+}
+
+fn target_entity(
+    target: Target,
+    enemy_query: &Query<(Entity, &Enemy)>,
+    hero_query: &Query<(Entity, &Hero)>,
+) -> Option<Entity> {
+    match target {
+        Target::Enemy(index) => enemy_query
+            .iter()
+            .find(|(_, enemy)| enemy.index == index)
+            .map(|(entity_id, _)| entity_id),
+        Target::Hero(index) => hero_query
+            .iter()
+            .find(|(_, hero)| hero.index == index)
+            .map(|(entity_id, _)| entity_id),
+    }
+}
+
+fn start_tweens(
+    mut action_event_reader: EventReader<ActionEvent>,
+    mut commands: Commands,
+    enemy_query: Query<(Entity, &Enemy)>,
+    hero_query: Query<(Entity, &Hero)>,
+    mut outcome_event_reader: EventReader<OutcomeEvent>,
+    transform_query: Query<(&Transform, Option<&Tween>)>,
+) {
+    let mut tweens = Vec::new();
+
+    for event in action_event_reader.read() {
+        let (actor, direction) = match **event {
+            Action::Enemy { enemy, .. } => (Target::Enemy(enemy), -1.0),
+            Action::Hero { hero, .. } => (Target::Hero(hero), 1.0),
+            _ => continue,
+        };
+
+        tweens.push((actor, TweenKind::Lunge(direction)));
+    }
+
+    let delay = if tweens.is_empty() {
+        0.0
+    } else {
+        LUNGE_SECONDS * 0.5
+    };
+
+    for event in outcome_event_reader.read() {
+        let tween = match **event {
+            BattleOutcome::Damaged { target, amount } if amount > 0 => (target, TweenKind::Shake),
+            BattleOutcome::Defeated { target } => (target, TweenKind::Fade),
+            BattleOutcome::Healed { target, .. } => (target, TweenKind::Flash),
+            _ => continue,
+        };
+
+        tweens.push(tween);
+    }
+
+    for (target, kind) in tweens {
+        let Some(entity_id) = target_entity(target, &enemy_query, &hero_query) else {
+            continue;
+        };
+
+        let Ok((transform, current)) = transform_query.get(entity_id) else {
+            continue;
+        };
+
+        // A new tween takes over from the last, so it starts from where that
+        // one would have ended
+        let origin = current.map_or(transform.translation, |tween| tween.origin);
+
+        commands.entity(entity_id).insert(Tween {
+            delay: if matches!(kind, TweenKind::Lunge(_)) {
+                0.0
+            } else {
+                delay
+            },
+            elapsed: 0.0,
+            kind,
+            origin,
+        });
+    }
+}
+
+fn animate_tweens(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tween_query: Query<(Entity, &mut Sprite, &mut Transform, &mut Tween)>,
+) {
+    for (entity_id, mut sprite, mut transform, mut tween) in tween_query.iter_mut() {
+        tween.elapsed += time.delta_seconds();
+
+        if tween.is_finished() {
+            // The hover colors take over again
+            transform.translation = tween.origin;
+            commands.entity(entity_id).remove::<Tween>();
+            continue;
+        }
+
+        let progress = tween.progress();
+        let mut offset = Vec3::ZERO;
+
+        match tween.kind {
+            TweenKind::Lunge(direction) => {
+                offset.y = direction * LUNGE_DISTANCE * (progress * PI).sin();
+            }
+            TweenKind::Shake => {
+                offset.x = SHAKE_DISTANCE * (1.0 - progress) * (progress * SHAKES * 2.0 * PI).sin();
+            }
+            TweenKind::Flash => {
+                sprite.color = FLASH_COLOR.mix(&Color::WHITE, progress);
+            }
+            TweenKind::Fade => {
+                sprite.color = Color::WHITE.mix(&FADED_COLOR, progress);
+            }
+        }
+
+        // Whole pixels keep the sprite crisp on the canvas
+        transform.translation = (tween.origin + offset).round();
+    }
+}
+
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        // After the rules have run, so an action and what it did start
+        // together
+        app.add_systems(
+            PostUpdate,
+            (start_tweens, animate_tweens)
+                .chain()
+                .in_set(TweenSystem)
+                .before(TransformSystem::TransformPropagate)
+                .run_if(in_state(AppState::Run)),
+        );
+    }
+}
@@ -0,0 +1,279 @@
+mod common;
+
+use bevy::{input::keyboard::Key, prelude::*};
+use cycle_the_heroes::{
+    battle::{Action, Intent, Target, HAND_SIZE},
+    battle_log::BattleLog,
+    card::Deck,
+    enemy::Enemy,
+    hero::{Hero, Player},
+    replay::{ReplayEntry, ReplayLog},
+    status::StatusKind,
+    tower::Floor,
+    tween::{Tween, FADED_COLOR},
+    AppState, BattleState, InfoPanelState,
+};
+
+use common::{
+    act, app_state, back_to_player, battle_state, enemies, info_panel_state, player, run_until,
+};
+
+const ATTACK: Action = Action::Hero {
+    hero: 0,
+    action: 0,
+    target: Target::Enemy(0),
+};
+
+#[test]
+fn starts_on_the_first_floor() {
+    let mut app = common::app();
+
+    assert!(back_to_player(&mut app));
+    assert_eq!(app.world().resource::<Floor>().current, 0);
+    assert_eq!(player(&app).heroes.len(), 3);
+    assert_eq!(enemies(&mut app).len(), 1);
+    assert_eq!(app.world().resource::<Deck>().state.hand.len(), HAND_SIZE);
+}
+
+#[test]
+fn attacking_damages_the_enemy() {
+    let mut app = common::app();
+
+    act(&mut app, ATTACK);
+
+    let enemy = &enemies(&mut app)[0];
+
+    assert_eq!(enemy.current_hp, enemy.max_hp - 1);
+}
+
+#[test]
+fn damage_floats_up_as_a_number() {
+    let mut app = common::app();
+
+    act(&mut app, ATTACK);
+    app.update();
+
+    let texts = app
+        .world_mut()
+        .query::<&Text>()
+        .iter(app.world())
+        .map(|text| text.sections[0].value.clone())
+        .collect::<Vec<_>>();
+
+    assert!(texts.iter().any(|text| text == "-1"));
+}
+
+#[test]
+fn attacks_animate_both_sides() {
+    let mut app = common::app();
+
+    act(&mut app, ATTACK);
+
+    let tweening = |app: &mut App| {
+        app.world_mut()
+            .query_filtered::<(Has<Hero>, Has<Enemy>), With<Tween>>()
+            .iter(app.world())
+            .collect::<Vec<_>>()
+    };
+
+    // The hero lunges and the enemy shakes
+    assert!(tweening(&mut app).contains(&(true, false)));
+    assert!(tweening(&mut app).contains(&(false, true)));
+
+    run_until(&mut app, |app| tweening(app).is_empty());
+}
+
+#[test]
+fn fallen_heroes_stay_faded_out() {
+    let mut app = common::app();
+
+    app.world_mut().resource_mut::<Player>().heroes[0].current_hp = 1;
+
+    act(
+        &mut app,
+        Action::Enemy {
+            enemy: 0,
+            attack: 0,
+            target: 0,
+        },
+    );
+
+    let color = |app: &mut App| {
+        app.world_mut()
+            .query::<(&Hero, &Sprite, Has<Tween>)>()
+            .iter(app.world())
+            .find(|(hero, _, _)| hero.index == 0)
+            .map(|(_, sprite, is_tweening)| (sprite.color, is_tweening))
+            .unwrap()
+    };
+
+    run_until(&mut app, |app| !color(app).1);
+    app.update();
+
+    assert_eq!(color(&mut app).0, FADED_COLOR);
+}
+
+#[test]
+fn enemies_show_their_move_before_the_heroes_act() {
+    let mut app = common::app();
+
+    assert!(back_to_player(&mut app));
+    app.update();
+
+    // The rat only bites, and always whoever is in front
+    assert_eq!(
+        enemies(&mut app)[0].intent,
+        Some(Intent {
+            attack: 0,
+            target: None
+        })
+    );
+}
+
+#[test]
+fn enemies_act_then_hand_back_to_the_player() {
+    let mut app = common::app();
+
+    act(&mut app, Action::Rest { hero: 0 });
+
+    run_until(&mut app, |app| battle_state(app) == BattleState::Enemy);
+    run_until(&mut app, back_to_player);
+
+    let player = player(&app);
+
+    // The rat bites whoever acted last, who then falls asleep
+    assert_eq!(player.heroes[0].current_hp, 5);
+    assert!(player.heroes[0].statuses.has(StatusKind::Sleep));
+    assert_ne!(player.current_hero, 0);
+}
+
+#[test]
+fn the_battle_log_numbers_messages_by_turn() {
+    let mut app = common::app();
+
+    act(&mut app, Action::Rest { hero: 0 });
+
+    run_until(&mut app, back_to_player);
+
+    let hero = player(&app).current_hero;
+
+    act(&mut app, Action::Rest { hero });
+
+    let log = &app.world().resource::<BattleLog>().entries;
+
+    assert!(log.len() >= 3);
+    assert_eq!(log[0].turn, 1);
+    assert!(log[0].message.ends_with("rests."));
+    assert_eq!(log[log.len() - 1].turn, 2);
+}
+
+#[test]
+fn defeating_the_last_enemy_climbs_the_tower() {
+    let mut app = common::app();
+
+    for mut enemy in app
+        .world_mut()
+        .query::<&mut Enemy>()
+        .iter_mut(app.world_mut())
+    {
+        enemy.current_hp = 1;
+    }
+
+    act(&mut app, ATTACK);
+
+    run_until(&mut app, |app| battle_state(app) == BattleState::Win);
+
+    run_until(&mut app, |app| app.world().resource::<Floor>().current == 1);
+    run_until(&mut app, back_to_player);
+
+    assert_eq!(enemies(&mut app).len(), 2);
+}
+
+#[test]
+fn losing_every_hero_loses_the_battle() {
+    let mut app = common::app();
+
+    {
+        let mut player = app.world_mut().resource_mut::<Player>();
+
+        player.heroes[0].current_hp = 1;
+        player.heroes[1].current_hp = 0;
+        player.heroes[2].current_hp = 0;
+    }
+
+    act(&mut app, Action::Rest { hero: 0 });
+
+    run_until(&mut app, |app| battle_state(app) == BattleState::Lose);
+}
+
+#[test]
+fn starting_over_after_a_loss_resets_the_run() {
+    let mut app = common::app();
+
+    {
+        let mut player = app.world_mut().resource_mut::<Player>();
+
+        player.heroes[0].current_hp = 1;
+        player.heroes[1].current_hp = 0;
+        player.heroes[2].current_hp = 0;
+    }
+
+    act(&mut app, Action::Rest { hero: 0 });
+
+    run_until(&mut app, |app| app_state(app) == AppState::GameOver);
+
+    // "New run" is the first option
+    common::press(&mut app, KeyCode::Enter, Key::Enter);
+
+    run_until(&mut app, back_to_player);
+
+    assert_eq!(app.world().resource::<Floor>().current, 0);
+    assert!(player(&app)
+        .heroes
+        .iter()
+        .all(|hero| hero.current_hp == hero.max_hp));
+
+    let enemies = enemies(&mut app);
+
+    assert!(!enemies.is_empty());
+    assert!(enemies
+        .iter()
+        .all(|enemy| enemy.state.current_hp == enemy.state.max_hp));
+}
+
+#[test]
+fn confirming_plays_a_card_from_the_hand() {
+    let mut app = common::app();
+
+    // The first card may need a target picked before it goes off
+    for _ in 0..2 {
+        if info_panel_state(&app) == InfoPanelState::Battle {
+            break;
+        }
+
+        common::press(&mut app, KeyCode::Enter, Key::Enter);
+    }
+
+    assert_eq!(info_panel_state(&app), InfoPanelState::Battle);
+
+    let deck = &app.world().resource::<Deck>().state;
+
+    assert_eq!(deck.hand.len(), HAND_SIZE - 1);
+    assert_eq!(deck.discard.len(), 1);
+}
+
+#[test]
+fn replays_play_back_recorded_choices() {
+    let mut app = common::replay_app(ReplayLog {
+        entries: vec![
+            ReplayEntry::Hero(1),
+            ReplayEntry::Action(Action::Rest { hero: 1 }),
+        ],
+        seed: 1,
+    });
+
+    run_until(&mut app, |app| battle_state(app) == BattleState::Enemy);
+    run_until(&mut app, back_to_player);
+
+    assert!(player(&app).heroes[1].statuses.has(StatusKind::Sleep));
+}
@@ -0,0 +1,161 @@
+// Builds the game the way `main` does, minus the window and renderer, and
+// drives it from the outside.
+
+use std::time::Duration;
+
+use bevy::{
+    asset::AssetMetaCheck,
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState, InputPlugin,
+    },
+    prelude::*,
+    state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
+    window::ExitCondition,
+};
+use cycle_the_heroes::{
+    battle::Action, enemy::Enemy, hero::Player, replay::ReplayLog, ActionEvent, AppState,
+    BattleState, GamePlugin, InfoPanelState,
+};
+
+// Plenty of frames for anything the game does between two decisions
+const MAX_FRAMES: usize = 200;
+
+pub fn app() -> App {
+    build(None)
+}
+
+pub fn replay_app(replay_log: ReplayLog) -> App {
+    build(Some(replay_log))
+}
+
+fn build(replay: Option<ReplayLog>) -> App {
+    let mut app = App::new();
+
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin {
+            meta_check: AssetMetaCheck::Never,
+            ..default()
+        },
+        HierarchyPlugin,
+        // Enemy sprites are part of their definitions, so images need loading
+        ImagePlugin::default(),
+        InputPlugin,
+        StatesPlugin,
+        TransformPlugin,
+        WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            ..default()
+        },
+        GamePlugin {
+            is_persistent: false,
+            replay,
+            seed: 1,
+        },
+    ))
+    // Normally registered by the rendering plugins
+    .init_asset::<AudioSource>()
+    .init_asset::<Font>()
+    .init_asset::<TextureAtlasLayout>()
+    // Every frame moves the clock on a quarter second, as fast as virtual time allows
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+        250,
+    )));
+
+    // `App::run` would normally do this, and some loaders only arrive here
+    app.finish();
+    app.cleanup();
+
+    // Assets load on other threads, so give them a moment
+    for _ in 0..1000 {
+        app.update();
+
+        match app_state(&app) {
+            AppState::Run => {
+                app.update();
+                return app;
+            }
+            // With nothing saved, a new run is the only option
+            AppState::Title => press(&mut app, KeyCode::Enter, Key::Enter),
+            _ => std::thread::sleep(Duration::from_millis(5)),
+        }
+    }
+
+    panic!("Game never finished loading");
+}
+
+pub fn app_state(app: &App) -> AppState {
+    app.world().resource::<State<AppState>>().get().clone()
+}
+
+pub fn battle_state(app: &App) -> BattleState {
+    app.world().resource::<State<BattleState>>().get().clone()
+}
+
+pub fn info_panel_state(app: &App) -> InfoPanelState {
+    app.world()
+        .resource::<State<InfoPanelState>>()
+        .get()
+        .clone()
+}
+
+pub fn player(app: &App) -> &Player {
+    app.world().resource::<Player>()
+}
+
+pub fn enemies(app: &mut App) -> Vec<Enemy> {
+    let mut enemies = app
+        .world_mut()
+        .query::<&Enemy>()
+        .iter(app.world())
+        .map(|enemy| Enemy {
+            index: enemy.index,
+            state: enemy.state.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    enemies.sort_by_key(|enemy| enemy.index);
+
+    enemies
+}
+
+// Sends an action the way the menus do
+pub fn act(app: &mut App, action: Action) {
+    app.world_mut()
+        .resource_mut::<NextState<InfoPanelState>>()
+        .set(InfoPanelState::Battle);
+    app.world_mut().send_event(ActionEvent(action));
+    app.update();
+}
+
+pub fn press(app: &mut App, key_code: KeyCode, logical_key: Key) {
+    for state in [ButtonState::Pressed, ButtonState::Released] {
+        app.world_mut().send_event(KeyboardInput {
+            key_code,
+            logical_key: logical_key.clone(),
+            state,
+            window: Entity::PLACEHOLDER,
+        });
+        app.update();
+    }
+}
+
+// Ticks until `done` holds, failing the test if it never does
+pub fn run_until(app: &mut App, done: impl Fn(&mut App) -> bool) {
+    for _ in 0..MAX_FRAMES {
+        if done(app) {
+            return;
+        }
+
+        app.update();
+    }
+
+    panic!("Gave up after {MAX_FRAMES} frames");
+}
+
+pub fn back_to_player(app: &mut App) -> bool {
+    battle_state(app) == BattleState::Player && info_panel_state(app) == InfoPanelState::Menu
+}